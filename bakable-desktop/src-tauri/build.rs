@@ -1,7 +1,34 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[allow(dead_code)]
+#[path = "src/rbxm.rs"]
+mod rbxm;
+
+#[allow(dead_code)]
+#[path = "src/project.rs"]
+mod project;
+
+/// Rojo project for the Studio plugin
+const PLUGIN_PROJECT: &str = "../../plugin/default.project.json";
+
+/// Public half of the release plugin signing key, hex; also embedded by
+/// src/plugin.rs
+const PLUGIN_PUBLIC_KEY: &str = "plugin.pub";
+
 fn main() {
     // Build Tauri
     tauri_build::build();
 
+    // Build Bakable.rbxm from the plugin sources
+    let plugin = build_plugin().unwrap_or_else(|e| panic!("Failed to build Studio plugin: {}", e));
+
+    // Version and checksum of the bundled Studio plugin, for the install manifest
+    embed_plugin_version(&plugin);
+
+    // Signature checked before the plugin is installed
+    sign_plugin(&plugin).unwrap_or_else(|e| panic!("Failed to sign Studio plugin: {}", e));
+
     // Build Swift code for ScreenCaptureKit
     #[cfg(target_os = "macos")]
     {
@@ -12,3 +39,103 @@ fn main() {
             .link();
     }
 }
+
+fn embed_plugin_version(plugin: &[u8]) {
+    use sha2::{Digest, Sha256};
+
+    println!("cargo:rerun-if-env-changed=BAKABLE_PLUGIN_VERSION");
+
+    let sha256 = hex_encode(&Sha256::digest(plugin));
+
+    // Released plugins share the app version unless the release sets its own
+    let version = std::env::var("BAKABLE_PLUGIN_VERSION")
+        .or_else(|_| std::env::var("CARGO_PKG_VERSION"))
+        .expect("CARGO_PKG_VERSION is always set for build scripts");
+
+    println!("cargo:rustc-env=BAKABLE_PLUGIN_VERSION={}", version);
+    println!("cargo:rustc-env=BAKABLE_PLUGIN_SHA256={}", sha256);
+}
+
+// MARK: - Plugin
+
+/// Assemble the plugin from its Rojo project into `$OUT_DIR/Bakable.rbxm`
+fn build_plugin() -> Result<Vec<u8>, String> {
+    let project_path = Path::new(PLUGIN_PROJECT);
+    // Covers the project file and every source under it
+    if let Some(dir) = project_path.parent() {
+        println!("cargo:rerun-if-changed={}", dir.display());
+    }
+
+    let bytes = project::build(project_path)?;
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").map_err(|e| e.to_string())?);
+    fs::write(out_dir.join("Bakable.rbxm"), &bytes)
+        .map_err(|e| format!("Failed to write Bakable.rbxm: {}", e))?;
+    Ok(bytes)
+}
+
+// MARK: - Signing
+
+/// Sign the plugin with `BAKABLE_PLUGIN_SIGNING_KEY` into
+/// `$OUT_DIR/Bakable.rbxm.sig`.
+///
+/// `BAKABLE_PLUGIN_SIGNING_KEY` is the hex ed25519 seed (64 characters)
+/// whose public half is in `plugin.pub`, the only key installs verify
+/// against; a seed for any other key fails the build. Release builds fail
+/// without the key. Debug builds without it get an empty signature, and
+/// install the bundled plugin unsigned.
+fn sign_plugin(plugin: &[u8]) -> Result<(), String> {
+    use ed25519_dalek::{Signer, SigningKey};
+
+    println!("cargo:rerun-if-env-changed=BAKABLE_PLUGIN_SIGNING_KEY");
+    println!("cargo:rerun-if-changed={}", PLUGIN_PUBLIC_KEY);
+
+    let release = std::env::var("PROFILE").as_deref() == Ok("release");
+    let signature = match std::env::var("BAKABLE_PLUGIN_SIGNING_KEY") {
+        Ok(seed) => {
+            let seed: [u8; 32] = hex_decode(seed.trim())
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or("BAKABLE_PLUGIN_SIGNING_KEY must be 64 hex characters")?;
+            let signing_key = SigningKey::from_bytes(&seed);
+
+            let pinned = fs::read_to_string(PLUGIN_PUBLIC_KEY)
+                .map_err(|e| format!("Failed to read {}: {}", PLUGIN_PUBLIC_KEY, e))?;
+            let derived = hex_encode(signing_key.verifying_key().as_bytes());
+            if !derived.eq_ignore_ascii_case(pinned.trim()) {
+                return Err(format!(
+                    "BAKABLE_PLUGIN_SIGNING_KEY is for public key {}, not the {} in {}",
+                    derived,
+                    pinned.trim(),
+                    PLUGIN_PUBLIC_KEY
+                ));
+            }
+
+            hex_encode(&signing_key.sign(plugin).to_bytes())
+        }
+        Err(_) if release => {
+            return Err("BAKABLE_PLUGIN_SIGNING_KEY is required for release builds".to_string());
+        }
+        Err(_) => {
+            println!("cargo:warning=Studio plugin is unsigned; debug builds install it anyway");
+            String::new()
+        }
+    };
+
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").map_err(|e| e.to_string())?);
+    fs::write(out_dir.join("Bakable.rbxm.sig"), signature)
+        .map_err(|e| format!("Failed to write Bakable.rbxm.sig: {}", e))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
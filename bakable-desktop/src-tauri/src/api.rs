@@ -0,0 +1,95 @@
+//! Error envelope shared by every helper route
+//!
+//! Failures use a status that matches the problem and the same JSON body:
+//! `{"code": "PERMISSION_DENIED", "message": "...", "retryable": false}`,
+//! plus `details` when there is more to say.
+
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Error returned by helper routes
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ApiError {
+    #[serde(skip)]
+    status: StatusCode,
+    /// Stable machine-readable code, e.g. `PERMISSION_DENIED`
+    pub code: &'static str,
+    /// Human-readable description
+    pub message: String,
+    /// Whether the same request may succeed if tried again later
+    pub retryable: bool,
+    /// Extra context, such as the field that failed validation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>, retryable: bool) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            retryable,
+            details: None,
+        }
+    }
+
+    /// 400: the request itself is wrong
+    pub fn bad_request(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, code, message, false)
+    }
+
+    /// 401: missing or wrong bearer token
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "UNAUTHORIZED", message, false)
+    }
+
+    /// 403: permission or origin not allowed
+    pub fn forbidden(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, code, message, false)
+    }
+
+    /// 404: no such route
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, "NOT_FOUND", message, false)
+    }
+
+    /// 503: a dependency (Studio, the recognizer) is not available right now
+    pub fn unavailable(code: &'static str, message: impl Into<String>) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, code, message, true)
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self::new(rejection.status(), "INVALID_BODY", rejection.body_text(), false)
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        Self::new(rejection.status(), "INVALID_QUERY", rejection.body_text(), false)
+    }
+}
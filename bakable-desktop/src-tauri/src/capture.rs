@@ -4,37 +4,85 @@
 //! using Apple's ScreenCaptureKit framework via Swift interop.
 
 use std::slice;
+use std::time::{Duration, Instant};
 use tracing::{info, error};
 
+use crate::events::{self, Event, Permission};
+use crate::{metrics, shutdown};
+
 // Link to Swift functions
+#[cfg(target_os = "macos")]
 extern "C" {
     fn check_screen_capture_permission() -> bool;
     fn request_screen_capture_permission();
     fn get_roblox_studio_window_id() -> i64;
+    fn is_roblox_studio_frontmost() -> bool;
     fn capture_roblox_studio_window() -> *mut std::ffi::c_void;
     fn sr_data_length(ptr: *mut std::ffi::c_void) -> usize;
     fn sr_data_bytes(ptr: *mut std::ffi::c_void) -> *const u8;
     fn sr_data_free(ptr: *mut std::ffi::c_void);
 }
 
+// Stand-ins where ScreenCaptureKit is unavailable: no permission, no window
+#[cfg(not(target_os = "macos"))]
+mod unsupported {
+    use std::ffi::c_void;
+
+    pub unsafe fn check_screen_capture_permission() -> bool {
+        false
+    }
+    pub unsafe fn request_screen_capture_permission() {}
+    pub unsafe fn get_roblox_studio_window_id() -> i64 {
+        0
+    }
+    pub unsafe fn is_roblox_studio_frontmost() -> bool {
+        false
+    }
+    pub unsafe fn capture_roblox_studio_window() -> *mut c_void {
+        std::ptr::null_mut()
+    }
+    pub unsafe fn sr_data_length(_ptr: *mut c_void) -> usize {
+        0
+    }
+    pub unsafe fn sr_data_bytes(_ptr: *mut c_void) -> *const u8 {
+        std::ptr::null()
+    }
+    pub unsafe fn sr_data_free(_ptr: *mut c_void) {}
+}
+
+#[cfg(not(target_os = "macos"))]
+use unsupported::*;
+
+// Swift FFI for window bounds (fast!)
+#[cfg(target_os = "macos")]
+extern "C" {
+    fn get_roblox_studio_window_bounds(
+        out_x: *mut i32,
+        out_y: *mut i32,
+        out_w: *mut i32,
+        out_h: *mut i32,
+    ) -> bool;
+}
+
 /// Capture a screenshot of the Roblox Studio window
 /// Returns PNG image data or None if capture failed
 pub fn capture_studio_viewport() -> Option<Vec<u8>> {
     info!("Attempting to capture Roblox Studio viewport");
+    let started = Instant::now();
 
     // Check permission first
     let has_permission = unsafe { check_screen_capture_permission() };
     if !has_permission {
         error!("Screen capture permission not granted");
         unsafe { request_screen_capture_permission() };
-        return None;
+        return capture_failed("PERMISSION_DENIED");
     }
 
     // Check if Roblox Studio is running
     let window_id = unsafe { get_roblox_studio_window_id() };
     if window_id == 0 {
         error!("Roblox Studio window not found");
-        return None;
+        return capture_failed("WINDOW_NOT_FOUND");
     }
 
     info!("Found Roblox Studio window with ID: {}", window_id);
@@ -44,7 +92,7 @@ pub fn capture_studio_viewport() -> Option<Vec<u8>> {
 
     if data_ptr.is_null() {
         error!("Failed to capture screenshot (null pointer returned)");
-        return None;
+        return capture_failed("CAPTURE_FAILED");
     }
 
     // Get data from Swift
@@ -54,7 +102,7 @@ pub fn capture_studio_viewport() -> Option<Vec<u8>> {
     if bytes_ptr.is_null() || length == 0 {
         error!("Failed to get screenshot data");
         unsafe { sr_data_free(data_ptr) };
-        return None;
+        return capture_failed("EMPTY_DATA");
     }
 
     // Copy data to Rust Vec
@@ -65,9 +113,90 @@ pub fn capture_studio_viewport() -> Option<Vec<u8>> {
     unsafe { sr_data_free(data_ptr) };
 
     info!("Captured {} bytes", result.len());
+    metrics::CAPTURE_DURATION.observe_duration(&[], started.elapsed());
+    metrics::CAPTURE_BYTES.observe(&[], result.len() as f64);
+    events::publish(Event::CaptureTaken { bytes: result.len() });
     Some(result)
 }
 
+/// Count a failed capture by error code
+fn capture_failed(code: &str) -> Option<Vec<u8>> {
+    metrics::CAPTURE_FAILURES.inc(&[("code", code)]);
+    None
+}
+
+/// Get the Roblox Studio window ID, or None if Studio has no window open
+pub fn get_studio_window_id() -> Option<i64> {
+    match unsafe { get_roblox_studio_window_id() } {
+        0 => None,
+        id => Some(id),
+    }
+}
+
+/// Check if Roblox Studio is the frontmost application
+pub fn is_studio_focused() -> bool {
+    unsafe { is_roblox_studio_frontmost() }
+}
+
+/// Check if a Roblox Studio process is running, with or without a window
+#[cfg(target_os = "macos")]
+pub fn is_studio_running() -> bool {
+    std::process::Command::new("pgrep")
+        .args(["-x", "RobloxStudio"])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Check if a Roblox Studio process is running, with or without a window
+#[cfg(target_os = "windows")]
+pub fn is_studio_running() -> bool {
+    use std::os::windows::process::CommandExt;
+
+    /// Keeps a console window from flashing up on every check
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    std::process::Command::new("tasklist")
+        .args(["/FI", "IMAGENAME eq RobloxStudioBeta.exe", "/NH"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .is_ok_and(|output| String::from_utf8_lossy(&output.stdout).contains("RobloxStudioBeta.exe"))
+}
+
+/// Check if a Roblox Studio process is running under Wine
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn is_studio_running() -> bool {
+    let Ok(entries) = std::fs::read_dir("/proc") else { return false };
+    entries.flatten().any(|entry| {
+        // `comm` is the executable name truncated to 15 bytes, e.g. "RobloxStudioBet"
+        std::fs::read_to_string(entry.path().join("comm"))
+            .is_ok_and(|comm| comm.starts_with("RobloxStudio"))
+    })
+}
+
+/// Get Roblox Studio window bounds via Swift FFI (fast!)
+#[cfg(target_os = "macos")]
+pub fn get_studio_window_bounds() -> Option<(i32, i32, i32, i32)> {
+    let mut x: i32 = 0;
+    let mut y: i32 = 0;
+    let mut w: i32 = 0;
+    let mut h: i32 = 0;
+
+    let found = unsafe {
+        get_roblox_studio_window_bounds(&mut x, &mut y, &mut w, &mut h)
+    };
+
+    if found {
+        Some((x, y, w, h))
+    } else {
+        None
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn get_studio_window_bounds() -> Option<(i32, i32, i32, i32)> {
+    None
+}
+
 /// Check if screen capture permission is granted
 pub fn has_capture_permission() -> bool {
     unsafe { check_screen_capture_permission() }
@@ -77,3 +206,21 @@ pub fn has_capture_permission() -> bool {
 pub fn request_permission() {
     unsafe { request_screen_capture_permission() }
 }
+
+/// Publish screen capture permission changes until shutdown
+pub async fn watch_permission() {
+    let mut granted = None;
+
+    while !shutdown::requested() {
+        let now_granted = has_capture_permission();
+        if granted != Some(now_granted) {
+            events::publish(Event::PermissionChanged {
+                permission: Permission::ScreenCapture,
+                granted: now_granted,
+            });
+            granted = Some(now_granted);
+        }
+
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
@@ -0,0 +1,460 @@
+//! Helper configuration and discovery
+//!
+//! Settings are read from `config.json` in the app data directory and can be
+//! overridden with environment variables. Once the server is bound, a
+//! `helper.json` discovery file is written next to it so the daemon and CLI
+//! can find the helper without assuming a fixed port or transport.
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn, Level};
+
+/// Bundle identifier, also the name of the app data directory
+pub const APP_IDENTIFIER: &str = "ai.bakable.desktop";
+
+/// Port used when neither config nor environment specify one
+pub const DEFAULT_PORT: u16 = 4850;
+
+const CONFIG_FILE: &str = "config.json";
+const DISCOVERY_FILE: &str = "helper.json";
+const SOCKET_FILE: &str = "helper.sock";
+const PORT_ENV: &str = "BAKABLE_HELPER_PORT";
+const TRANSPORT_ENV: &str = "BAKABLE_HELPER_TRANSPORT";
+const LOG_LEVEL_ENV: &str = "BAKABLE_LOG_LEVEL";
+const NODE_PATH_ENV: &str = "BAKABLE_NODE_PATH";
+const DAEMON_ROOT_ENV: &str = "BAKABLE_DAEMON_ROOT";
+const PLUGIN_DEV_ENV: &str = "BAKABLE_PLUGIN_DEV";
+const DAEMON_TOKEN_FILE: &str = "daemon-token";
+
+/// Auth token the daemon accepts; also read by the daemon itself
+pub const DAEMON_TOKEN_ENV: &str = "BAKABLE_TOKEN";
+
+/// Beta plugin looked for in the app data directory when not configured
+const BETA_PLUGIN_FILE: &str = "Bakable-beta.rbxm";
+
+/// Whisper model looked for in the app data directory when not configured
+const WHISPER_MODEL_FILE: &str = "models/ggml-base.en.bin";
+
+/// Project file looked for when dev mode is pointed at a directory
+const PROJECT_FILE: &str = "default.project.json";
+
+/// Overrides the Roblox plugins directory the plugin is installed to
+pub const PLUGINS_DIR_ENV: &str = "BAKABLE_PLUGINS_DIR";
+
+/// Where bundled apps commonly find Node when it isn't on the GUI app's PATH
+const NODE_CANDIDATES: &[&str] = &["/opt/homebrew/bin/node", "/usr/local/bin/node"];
+
+/// Get the app data directory (same location Tauri uses for this bundle)
+pub fn app_data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_IDENTIFIER))
+}
+
+/// Get the app log directory (same location Tauri uses for this bundle)
+pub fn app_log_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    let dir = dirs::home_dir().map(|dir| dir.join("Library/Logs").join(APP_IDENTIFIER));
+
+    #[cfg(not(target_os = "macos"))]
+    let dir = dirs::config_dir().map(|dir| dir.join(APP_IDENTIFIER).join("logs"));
+
+    dir
+}
+
+/// Get the path of the helper's Unix domain socket
+pub fn socket_path() -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join(SOCKET_FILE))
+}
+
+/// Listeners serving the helper API
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    /// TCP on localhost
+    #[default]
+    Tcp,
+    /// Unix domain socket only; no TCP port is opened
+    Unix,
+    /// TCP and the Unix domain socket
+    Both,
+}
+
+impl Transport {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "tcp" => Some(Self::Tcp),
+            "unix" => Some(Self::Unix),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    /// Check if a TCP listener is needed
+    pub fn uses_tcp(self) -> bool {
+        matches!(self, Self::Tcp | Self::Both)
+    }
+
+    /// Check if a Unix socket listener is needed
+    pub fn uses_unix(self) -> bool {
+        matches!(self, Self::Unix | Self::Both)
+    }
+}
+
+/// User configuration for the helper
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Preferred HTTP port
+    pub port: Option<u16>,
+    /// Serve over TCP, a Unix domain socket, or both
+    pub transport: Transport,
+    /// Extra browser origins allowed to call the helper
+    pub allowed_origins: Vec<String>,
+    /// Minimum log level (`error`, `warn`, `info`, `debug` or `trace`)
+    pub log_level: Option<String>,
+    /// Node daemon supervision
+    pub daemon: DaemonConfig,
+    /// Roblox plugins directory, for Studio installs the helper can't find
+    pub plugins_dir: Option<PathBuf>,
+    /// Dev-mode plugin hot reload
+    pub plugin_dev: PluginDevConfig,
+    /// Plugin builds for the beta and dev release channels
+    pub plugin_channels: PluginChannelsConfig,
+    /// Offline speech recognition
+    pub speech: SpeechConfig,
+}
+
+impl Config {
+    /// Load `config.json`, falling back to defaults if missing or invalid
+    pub fn load() -> Self {
+        let Some(path) = app_data_dir().map(|dir| dir.join(CONFIG_FILE)) else {
+            return Self::default();
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                warn!("Ignoring invalid config {:?}: {}", path, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Preferred port: `BAKABLE_HELPER_PORT`, then config, then 4850
+    pub fn port(&self) -> u16 {
+        std::env::var(PORT_ENV)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+            .or(self.port)
+            .unwrap_or(DEFAULT_PORT)
+    }
+
+    /// Transport: `BAKABLE_HELPER_TRANSPORT`, then config, then TCP.
+    /// Unix sockets are unavailable on Windows, which always uses TCP.
+    pub fn transport(&self) -> Transport {
+        let transport = std::env::var(TRANSPORT_ENV)
+            .ok()
+            .and_then(|value| Transport::parse(&value))
+            .unwrap_or(self.transport);
+
+        if cfg!(unix) || transport == Transport::Tcp {
+            transport
+        } else {
+            warn!("Unix socket transport is not supported on this platform, using TCP");
+            Transport::Tcp
+        }
+    }
+
+    /// Log level: `BAKABLE_LOG_LEVEL`, then config, then info
+    pub fn log_level(&self) -> Level {
+        std::env::var(LOG_LEVEL_ENV)
+            .ok()
+            .or_else(|| self.log_level.clone())
+            .and_then(|level| level.trim().parse().ok())
+            .unwrap_or(Level::INFO)
+    }
+}
+
+/// Settings for rebuilding and reinstalling the plugin as its sources change
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PluginDevConfig {
+    pub enabled: bool,
+    /// Rojo project file, or a directory containing `default.project.json`
+    pub project: Option<PathBuf>,
+    /// Quiet period after the last change before rebuilding
+    pub debounce_ms: Option<u64>,
+}
+
+impl PluginDevConfig {
+    /// Project file to watch: `BAKABLE_PLUGIN_DEV` (which also enables dev
+    /// mode), then config if enabled. None when dev mode is off.
+    pub fn project_file(&self) -> Option<PathBuf> {
+        let project = std::env::var_os(PLUGIN_DEV_ENV)
+            .map(PathBuf::from)
+            .or_else(|| self.project.clone().filter(|_| self.enabled))?;
+        match project.is_dir() {
+            true => Some(project.join(PROJECT_FILE)),
+            false => Some(project),
+        }
+    }
+}
+
+/// Where the non-stable plugin channels get their builds. Stable is always
+/// the plugin bundled with the app.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PluginChannelsConfig {
+    /// Beta `.rbxm`
+    pub beta: Option<PathBuf>,
+    /// Dev `.rbxm` or Rojo project; defaults to the `plugin_dev` project
+    pub dev: Option<PathBuf>,
+}
+
+impl PluginChannelsConfig {
+    /// Beta plugin: config, then `Bakable-beta.rbxm` in the app data directory
+    pub fn beta_path(&self) -> Option<PathBuf> {
+        self.beta
+            .clone()
+            .or_else(|| app_data_dir().map(|dir| dir.join(BETA_PLUGIN_FILE)))
+    }
+
+    /// Dev build: config (a directory means its `default.project.json`),
+    /// then the dev-mode project
+    pub fn dev_path(&self, plugin_dev: &PluginDevConfig) -> Option<PathBuf> {
+        match &self.dev {
+            Some(dev) if dev.is_dir() => Some(dev.join(PROJECT_FILE)),
+            Some(dev) => Some(dev.clone()),
+            None => plugin_dev.project_file(),
+        }
+    }
+}
+
+/// Offline speech recognition, used where the platform has no recognizer
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SpeechConfig {
+    /// Whisper model in ggml format
+    pub model: Option<PathBuf>,
+    /// Spoken language, e.g. `en`; detected when unset
+    pub language: Option<String>,
+    pub audio_source: AudioSourceConfig,
+}
+
+impl SpeechConfig {
+    /// Model file: config, then `models/ggml-base.en.bin` in the app data
+    /// directory
+    pub fn model_path(&self) -> Option<PathBuf> {
+        self.model
+            .clone()
+            .or_else(|| app_data_dir().map(|dir| dir.join(WHISPER_MODEL_FILE)))
+    }
+}
+
+/// Where offline speech recognition reads 16 kHz mono 16-bit PCM from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum AudioSourceConfig {
+    /// Default microphone through PulseAudio, else ALSA
+    #[default]
+    Microphone,
+    /// Program writing raw little-endian PCM to stdout, e.g. `pw-record`
+    Command { command: Vec<String> },
+    /// WAV file, or raw PCM file or FIFO
+    File { path: PathBuf },
+}
+
+/// Settings for the supervised Node daemon (`daemon/index.js`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Spawn and supervise the daemon from the app
+    pub enabled: bool,
+    /// Node executable
+    pub node_path: Option<PathBuf>,
+    /// Checkout containing `daemon/index.js`
+    pub repo_path: Option<PathBuf>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            node_path: None,
+            repo_path: None,
+        }
+    }
+}
+
+impl DaemonConfig {
+    /// Node executable: `BAKABLE_NODE_PATH`, then config, then a well-known
+    /// install location, then `node` from PATH
+    pub fn node_path(&self) -> PathBuf {
+        std::env::var_os(NODE_PATH_ENV)
+            .map(PathBuf::from)
+            .or_else(|| self.node_path.clone())
+            .or_else(|| {
+                NODE_CANDIDATES
+                    .iter()
+                    .map(PathBuf::from)
+                    .find(|path| path.exists())
+            })
+            .unwrap_or_else(|| PathBuf::from("node"))
+    }
+
+    /// Daemon checkout: `BAKABLE_DAEMON_ROOT`, then config
+    pub fn repo_path(&self) -> Option<PathBuf> {
+        std::env::var_os(DAEMON_ROOT_ENV)
+            .map(PathBuf::from)
+            .or_else(|| self.repo_path.clone())
+    }
+}
+
+/// Auth token shared by the daemon and the Studio plugin: `BAKABLE_TOKEN`,
+/// then the token saved in the app data directory, created on first use.
+/// It stays the same across restarts so installed plugins keep working.
+pub fn daemon_token() -> Result<String, String> {
+    if let Ok(token) = std::env::var(DAEMON_TOKEN_ENV) {
+        return Ok(token);
+    }
+
+    let path = app_data_dir()
+        .map(|dir| dir.join(DAEMON_TOKEN_FILE))
+        .ok_or_else(|| "Could not determine app data directory".to_string())?;
+    if let Ok(token) = fs::read_to_string(&path) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_string());
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let token = random_token();
+    write_private(&path, token.as_bytes())
+        .map_err(|e| format!("Failed to write daemon token: {}", e))?;
+    info!("Daemon token created at {:?}", path);
+    Ok(token)
+}
+
+/// 32 random bytes, hex encoded
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Write a file readable only by the current user, narrowing the permissions
+/// of an existing one before anything is written to it
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)
+}
+
+/// Contents of the discovery file read by the daemon and CLI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Discovery {
+    /// TCP port, if serving over TCP
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// Unix socket path, if serving over a socket
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket: Option<PathBuf>,
+    pub pid: u32,
+    pub token: String,
+    pub version: String,
+}
+
+impl Discovery {
+    /// Describe this process bound to `port` and/or `socket`, accepting
+    /// requests that carry `token`
+    pub fn new(port: Option<u16>, socket: Option<PathBuf>, token: String) -> Self {
+        Self {
+            port,
+            socket,
+            pid: std::process::id(),
+            token,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Get the discovery file path
+    pub fn path() -> Option<PathBuf> {
+        app_data_dir().map(|dir| dir.join(DISCOVERY_FILE))
+    }
+
+    /// Write the discovery file, readable only by the current user
+    pub fn write(&self) -> Result<PathBuf, String> {
+        let path = Self::path()
+            .ok_or_else(|| "Could not determine app data directory".to_string())?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+
+        let contents = serde_json::to_vec_pretty(self)
+            .map_err(|e| format!("Failed to serialize discovery file: {}", e))?;
+
+        write_private(&path, &contents)
+            .map_err(|e| format!("Failed to write discovery file: {}", e))?;
+
+        info!("Discovery file written to {:?}", path);
+        Ok(path)
+    }
+
+    /// The discovery file this process wrote, if any
+    pub fn read() -> Option<Self> {
+        let contents = fs::read(Self::path()?).ok()?;
+        serde_json::from_slice::<Discovery>(&contents)
+            .ok()
+            .filter(|discovery| discovery.pid == std::process::id())
+    }
+
+    /// Remove the discovery file if it belongs to this process
+    pub fn remove() {
+        let Some(path) = Self::path() else { return };
+
+        if Self::read().is_some() {
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Failed to remove discovery file: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn write_private_narrows_existing_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("helper.json");
+        fs::write(&path, "{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        write_private(&path, b"secret").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        assert_eq!(fs::read(&path).unwrap(), b"secret");
+    }
+}
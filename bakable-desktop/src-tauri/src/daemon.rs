@@ -0,0 +1,261 @@
+//! Node daemon supervisor
+//!
+//! Spawns `daemon/index.js` from the configured checkout, forwards its
+//! stdout/stderr into the app log, and restarts it with exponential backoff
+//! when it exits. If something is already listening on the daemon port (a
+//! daemon started by hand), it is left alone and reported as external.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tracing::{error, info, warn};
+
+use crate::config::{self, DaemonConfig, DAEMON_TOKEN_ENV};
+use crate::events::{self, Event};
+
+/// Port the daemon serves its HTTP API on
+pub const DAEMON_PORT: u16 = 4849;
+
+const DAEMON_SCRIPT: &str = "daemon/index.js";
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A run this long counts as healthy and resets the backoff
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+/// How often to re-check a daemon we didn't start
+const EXTERNAL_RECHECK: Duration = Duration::from_secs(10);
+
+/// How long to wait for the daemon port to accept a connection
+const PORT_CHECK_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How long the daemon gets to exit after SIGTERM before it is killed
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Supervisor state, shown in the tray and pushed to `/ws` clients
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum DaemonState {
+    Disabled,
+    NotConfigured,
+    Starting,
+    Running { pid: u32 },
+    /// A daemon not started by us is serving the port
+    External,
+    Restarting { attempt: u32, in_secs: u64 },
+    Failed { error: String },
+    Stopped,
+}
+
+impl DaemonState {
+    /// Short label for the tray menu
+    pub fn label(&self) -> String {
+        match self {
+            Self::Disabled => "Daemon: Disabled".to_string(),
+            Self::NotConfigured => "Daemon: Not configured".to_string(),
+            Self::Starting => "Daemon: Starting...".to_string(),
+            Self::Running { pid } => format!("Daemon: Running (pid {})", pid),
+            Self::External => "Daemon: Running (external)".to_string(),
+            Self::Restarting { attempt, in_secs } => {
+                format!("Daemon: Restarting in {}s (attempt {})", in_secs, attempt)
+            }
+            Self::Failed { error } => format!("Daemon: Failed ({})", error),
+            Self::Stopped => "Daemon: Stopped".to_string(),
+        }
+    }
+}
+
+/// Set to request shutdown of the supervisor
+static SHUTDOWN: OnceLock<watch::Sender<bool>> = OnceLock::new();
+/// True while `run` is supervising
+static RUNNING: OnceLock<watch::Sender<bool>> = OnceLock::new();
+static STATE: Mutex<Option<DaemonState>> = Mutex::new(None);
+
+fn shutdown_channel() -> &'static watch::Sender<bool> {
+    SHUTDOWN.get_or_init(|| watch::channel(false).0)
+}
+
+fn running_channel() -> &'static watch::Sender<bool> {
+    RUNNING.get_or_init(|| watch::channel(false).0)
+}
+
+/// Current supervisor state
+pub fn current_state() -> DaemonState {
+    STATE
+        .lock()
+        .ok()
+        .and_then(|state| state.clone())
+        .unwrap_or(DaemonState::Stopped)
+}
+
+/// Supervise the daemon until `stop` is called. `on_state` is called on
+/// every state change.
+pub async fn run(config: DaemonConfig, on_state: impl Fn(&DaemonState) + Send + Sync) {
+    let set_state = |state: DaemonState| {
+        info!("{}", state.label());
+        if let Ok(mut current) = STATE.lock() {
+            *current = Some(state.clone());
+        }
+        on_state(&state);
+        events::publish(Event::DaemonStateChanged { state });
+    };
+
+    if !config.enabled {
+        set_state(DaemonState::Disabled);
+        return;
+    }
+
+    running_channel().send_replace(true);
+    supervise(config, shutdown_channel().subscribe(), &set_state).await;
+    running_channel().send_replace(false);
+}
+
+/// Stop the daemon and wait for the supervisor to finish
+pub async fn stop() {
+    shutdown_channel().send_replace(true);
+
+    let mut running = running_channel().subscribe();
+    let stopped = running.wait_for(|running| !running);
+    if tokio::time::timeout(STOP_TIMEOUT * 2, stopped).await.is_err() {
+        warn!("Daemon supervisor did not stop in time");
+    }
+}
+
+/// Whether anything answers on the daemon port within `timeout`
+pub async fn is_reachable(timeout: Duration) -> bool {
+    let connect = tokio::net::TcpStream::connect(("127.0.0.1", DAEMON_PORT));
+    matches!(tokio::time::timeout(timeout, connect).await, Ok(Ok(_)))
+}
+
+/// Sleep for `duration`, returning true if shutdown was requested meanwhile
+async fn sleep_or_shutdown(duration: Duration, shutdown: &mut watch::Receiver<bool>) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => *shutdown.borrow(),
+        _ = shutdown.changed() => true,
+    }
+}
+
+async fn supervise(
+    config: DaemonConfig,
+    mut shutdown: watch::Receiver<bool>,
+    set_state: &impl Fn(DaemonState),
+) {
+    let Some(repo_path) = config.repo_path() else {
+        set_state(DaemonState::NotConfigured);
+        return;
+    };
+    let node_path = config.node_path();
+    // The installed plugin authenticates with this token
+    let token = config::daemon_token()
+        .inspect_err(|e| warn!("Daemon will generate its own token: {}", e))
+        .ok();
+
+    let mut backoff = MIN_BACKOFF;
+    let mut attempt: u32 = 0;
+
+    while !*shutdown.borrow() {
+        if is_reachable(PORT_CHECK_TIMEOUT).await {
+            set_state(DaemonState::External);
+            if sleep_or_shutdown(EXTERNAL_RECHECK, &mut shutdown).await {
+                break;
+            }
+            continue;
+        }
+
+        set_state(DaemonState::Starting);
+        let mut command = Command::new(&node_path);
+        command
+            .arg(DAEMON_SCRIPT)
+            .current_dir(&repo_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(token) = &token {
+            command.env(DAEMON_TOKEN_ENV, token);
+        }
+        let spawned = command.spawn();
+
+        match spawned {
+            Ok(mut child) => {
+                let pid = child.id().unwrap_or_default();
+                set_state(DaemonState::Running { pid });
+
+                if let Some(stdout) = child.stdout.take() {
+                    tokio::spawn(forward_output(stdout, false));
+                }
+                if let Some(stderr) = child.stderr.take() {
+                    tokio::spawn(forward_output(stderr, true));
+                }
+
+                let started = Instant::now();
+                tokio::select! {
+                    status = child.wait() => {
+                        match status {
+                            Ok(status) => warn!("Daemon exited: {}", status),
+                            Err(e) => error!("Failed to wait for daemon: {}", e),
+                        }
+                        if started.elapsed() >= STABLE_AFTER {
+                            backoff = MIN_BACKOFF;
+                            attempt = 0;
+                        }
+                    }
+                    _ = shutdown.changed() => {
+                        terminate(&mut child).await;
+                        break;
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to spawn daemon with {:?}: {}", node_path, e);
+                set_state(DaemonState::Failed { error: e.to_string() });
+            }
+        }
+
+        attempt += 1;
+        set_state(DaemonState::Restarting {
+            attempt,
+            in_secs: backoff.as_secs(),
+        });
+        if sleep_or_shutdown(backoff, &mut shutdown).await {
+            break;
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+
+    set_state(DaemonState::Stopped);
+}
+
+/// Log each line the daemon writes
+async fn forward_output(stream: impl AsyncRead + Unpin, is_stderr: bool) {
+    let mut lines = BufReader::new(stream).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if is_stderr {
+            warn!(target: "daemon", "{}", line);
+        } else {
+            info!(target: "daemon", "{}", line);
+        }
+    }
+}
+
+/// Ask the daemon to exit, killing it if it doesn't within `STOP_TIMEOUT`
+async fn terminate(child: &mut Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        info!("Stopping daemon (pid {})", pid);
+        unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) };
+        if tokio::time::timeout(STOP_TIMEOUT, child.wait()).await.is_ok() {
+            return;
+        }
+        warn!("Daemon did not exit after SIGTERM, killing it");
+    }
+
+    if let Err(e) = child.kill().await {
+        error!("Failed to kill daemon: {}", e);
+    }
+}
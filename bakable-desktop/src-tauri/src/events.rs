@@ -0,0 +1,88 @@
+//! Helper event bus
+//!
+//! Capture, speech and the snap monitor publish typed events here; the
+//! `/ws` endpoint forwards them to connected clients so they don't have to
+//! poll `/speech/transcription` or `/health`.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+use crate::daemon::DaemonState;
+
+/// Events buffered per subscriber before it starts lagging
+const CAPACITY: usize = 256;
+
+static BUS: OnceLock<broadcast::Sender<Event>> = OnceLock::new();
+
+/// Permission an event refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    ScreenCapture,
+    Speech,
+}
+
+/// Event pushed to `/ws` clients, serialized as `{"type": "...", ...}`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// Recognition in progress; text may still change
+    TranscriptPartial { text: String },
+    /// Recognition session ended with this text
+    TranscriptFinal { text: String },
+    SpeakingStarted,
+    SpeakingFinished,
+    CaptureTaken { bytes: usize },
+    SnapChanged { enabled: bool },
+    StudioWindowFound { x: i32, y: i32, width: i32, height: i32 },
+    StudioWindowLost,
+    PermissionChanged { permission: Permission, granted: bool },
+    DaemonStateChanged { state: DaemonState },
+    /// Dev mode rebuilt and reinstalled the plugin
+    PluginReloaded { changed: Vec<String> },
+    /// Dev mode couldn't build the plugin from source
+    PluginBuildFailed { error: String },
+    /// The subscriber fell behind and missed `skipped` events
+    Lagged { skipped: u64 },
+}
+
+fn bus() -> &'static broadcast::Sender<Event> {
+    BUS.get_or_init(|| broadcast::channel(CAPACITY).0)
+}
+
+/// Publish an event to all current subscribers
+pub fn publish(event: Event) {
+    // Err only means nobody is subscribed right now
+    let _ = bus().send(event);
+}
+
+/// Subscribe to events published from now on
+pub fn subscribe() -> broadcast::Receiver<Event> {
+    bus().subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_serialize_with_type_tag() {
+        let json = serde_json::to_value(Event::PermissionChanged {
+            permission: Permission::ScreenCapture,
+            granted: true,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "permission_changed",
+                "permission": "screen_capture",
+                "granted": true,
+            })
+        );
+
+        let json = serde_json::to_value(Event::SpeakingFinished).unwrap();
+        assert_eq!(json, serde_json::json!({ "type": "speaking_finished" }));
+    }
+}
@@ -0,0 +1,200 @@
+//! Dev-mode plugin hot reload
+//!
+//! Watches the plugin's Rojo project file and the `$path` sources it maps,
+//! rebuilds `Bakable.rbxm` once edits settle, and installs it through the
+//! same path as `install_plugin`.
+//! Studio picks up the new file from its plugins folder on its own.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::config::PluginDevConfig;
+use crate::events::{self, Event};
+use crate::{plugin, project, shutdown};
+
+/// How often the sources are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Default quiet period after the last change before rebuilding
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Most changed paths listed in a notification
+const MAX_LISTED: usize = 5;
+
+/// Directories never part of a plugin build, even under a `$path`
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target"];
+
+/// Hash of the path, size and modification time of every file in `paths`
+/// and under those that are directories, skipping dot entries
+fn fingerprint(paths: &[PathBuf]) -> u64 {
+    fn visit(path: &Path, hasher: &mut DefaultHasher) {
+        let Ok(metadata) = fs::metadata(path) else { return };
+        path.hash(hasher);
+        if !metadata.is_dir() {
+            metadata.len().hash(hasher);
+            metadata.modified().ok().hash(hasher);
+            return;
+        }
+
+        let Ok(entries) = fs::read_dir(path) else { return };
+        let mut children: Vec<PathBuf> = entries
+            .flatten()
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref())
+            })
+            .map(|entry| entry.path())
+            .collect();
+        children.sort();
+        for child in children {
+            visit(&child, hasher);
+        }
+    }
+
+    let mut hasher = DefaultHasher::new();
+    for path in paths {
+        visit(path, &mut hasher);
+    }
+    hasher.finish()
+}
+
+/// `fingerprint` of the project file and its sources, off the async runtime
+async fn fingerprint_sources(project_file: &Path) -> u64 {
+    let project_file = project_file.to_path_buf();
+    tokio::task::spawn_blocking(move || fingerprint(&project::source_paths(&project_file)))
+        .await
+        .unwrap_or_default()
+}
+
+/// Notification text for a reload
+fn describe(changed: &[String]) -> String {
+    if changed.is_empty() {
+        return "Plugin rebuilt".to_string();
+    }
+    let mut listed = changed[..changed.len().min(MAX_LISTED)].join(", ");
+    if changed.len() > MAX_LISTED {
+        listed.push_str(&format!(", and {} more", changed.len() - MAX_LISTED));
+    }
+    format!("Plugin reloaded: {}", listed)
+}
+
+/// Rebuild and reinstall on every settled change until shutdown. `notify`
+/// receives a title and body for each reload or build failure; `installed`
+/// runs after each successful install. The build at startup is installed
+/// without a notification or reload event.
+pub async fn run(
+    config: PluginDevConfig,
+    notify: impl Fn(&str, &str) + Send + Sync,
+    installed: impl Fn() + Send + Sync,
+) {
+    let Some(project_file) = config.project_file() else { return };
+    let debounce = config.debounce_ms.map(Duration::from_millis).unwrap_or(DEFAULT_DEBOUNCE);
+    info!("Plugin dev mode: watching {:?}", project::source_paths(&project_file));
+
+    let mut last_build: Option<Vec<u8>> = None;
+    let mut built_at: Option<u64> = None;
+    let mut seen = fingerprint_sources(&project_file).await;
+    let mut quiet = Duration::ZERO;
+
+    while !shutdown::requested() {
+        if built_at != Some(seen) && quiet >= debounce {
+            built_at = Some(seen);
+
+            let file = project_file.clone();
+            let built = tokio::task::spawn_blocking(move || project::build(&file))
+                .await
+                .unwrap_or_else(|e| Err(format!("Build task failed: {}", e)));
+
+            match built {
+                Ok(bytes) if last_build.as_ref() == Some(&bytes) => {}
+                Ok(bytes) => {
+                    let build = bytes.clone();
+                    let result = tokio::task::spawn_blocking(move || plugin::install_dev_build(&build))
+                        .await
+                        .unwrap_or_else(|e| Err(format!("Install task failed: {}", e)));
+                    match result {
+                        Ok(_) => {
+                            match &last_build {
+                                Some(previous) => {
+                                    let changed = plugin::changed_paths(previous, &bytes).unwrap_or_default();
+                                    info!("Plugin dev build installed ({} changed)", changed.len());
+                                    notify("Bakable Dev", &describe(&changed));
+                                    events::publish(Event::PluginReloaded { changed });
+                                }
+                                None => info!("Plugin dev build installed"),
+                            }
+                            installed();
+                            last_build = Some(bytes);
+                        }
+                        Err(e) => {
+                            error!("Failed to install plugin dev build: {}", e);
+                            notify("Bakable Dev Error", &format!("Failed to install plugin: {}", e));
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Plugin build failed: {}", e);
+                    notify("Bakable Dev Error", &format!("Plugin build failed: {}", e));
+                    events::publish(Event::PluginBuildFailed { error: e });
+                }
+            }
+        }
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+        // Re-read each time so `$path`s added to the project are picked up
+        let current = fingerprint_sources(&project_file).await;
+        if current == seen {
+            quiet += POLL_INTERVAL;
+        } else {
+            seen = current;
+            quiet = Duration::ZERO;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_tracks_edits_and_new_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("src/node_modules")).unwrap();
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("src/init.server.lua"), "print(1)").unwrap();
+        let project = dir.join("default.project.json");
+        fs::write(&project, r#"{"name": "Demo", "tree": {"$path": "src"}}"#).unwrap();
+
+        let paths = project::source_paths(&project);
+        assert_eq!(paths, vec![project.clone(), dir.join("src")]);
+        let before = fingerprint(&paths);
+        assert_eq!(fingerprint(&paths), before);
+
+        fs::write(dir.join("src/init.server.lua"), "print(12)").unwrap();
+        let edited = fingerprint(&paths);
+        assert_ne!(edited, before);
+
+        fs::write(dir.join("src/Extra.lua"), "").unwrap();
+        let added = fingerprint(&paths);
+        assert_ne!(added, edited);
+
+        // Files outside the sources, dependencies and dot files don't count
+        fs::write(dir.join("target/build.log"), "").unwrap();
+        fs::write(dir.join("README.md"), "").unwrap();
+        fs::write(dir.join("src/node_modules/x.lua"), "").unwrap();
+        fs::write(dir.join("src/.DS_Store"), "").unwrap();
+        assert_eq!(fingerprint(&paths), added);
+
+        let many: Vec<String> = (0..7).map(|i| format!("Bakable/M{}", i)).collect();
+        assert_eq!(
+            describe(&many),
+            "Plugin reloaded: Bakable/M0, Bakable/M1, Bakable/M2, Bakable/M3, Bakable/M4, and 2 more"
+        );
+    }
+}
@@ -0,0 +1,341 @@
+//! Logging to stdout, rotating files and an in-memory buffer
+//!
+//! Bundled apps have no visible stdout, so logs are also written to
+//! `helper.log` in the app log directory (rotated by size and by day) and
+//! kept in a ring buffer that `GET /logs` and the `get_recent_logs` command
+//! read from.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, Layer};
+
+use crate::config::{self, Config};
+
+const LOG_FILE: &str = "helper.log";
+
+/// Rotate once the current file grows past this size
+const MAX_FILE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Rotated files kept alongside the current one
+const MAX_ROTATED_FILES: usize = 5;
+
+/// Entries kept in memory for `/logs`
+const RECENT_CAPACITY: usize = 2000;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+
+static LOG_FILE_WRITER: OnceLock<Mutex<RotatingFile>> = OnceLock::new();
+static RECENT: Mutex<VecDeque<LogEntry>> = Mutex::new(VecDeque::new());
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// MARK: - Rotating File
+
+/// Log file rotated when it exceeds `MAX_FILE_BYTES` or the UTC day changes.
+/// `helper.log.1` is the most recent rotated file.
+struct RotatingFile {
+    dir: PathBuf,
+    file: Option<File>,
+    size: u64,
+    day: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let mut rotating = Self {
+            dir,
+            file: None,
+            size: 0,
+            day: unix_now() / SECONDS_PER_DAY,
+        };
+        rotating.reopen()?;
+        Ok(rotating)
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        match index {
+            0 => self.dir.join(LOG_FILE),
+            n => self.dir.join(format!("{}.{}", LOG_FILE, n)),
+        }
+    }
+
+    fn reopen(&mut self) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path(0))?;
+        self.size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        self.file = Some(file);
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        let _ = fs::remove_file(self.path(MAX_ROTATED_FILES));
+        for index in (0..MAX_ROTATED_FILES).rev() {
+            let from = self.path(index);
+            if from.exists() {
+                fs::rename(&from, self.path(index + 1))?;
+            }
+        }
+        self.reopen()
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let day = unix_now() / SECONDS_PER_DAY;
+        if self.size > 0 && (self.size + buf.len() as u64 > MAX_FILE_BYTES || day != self.day) {
+            self.day = day;
+            self.rotate()?;
+        }
+
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| io::Error::other("log file not open"))?;
+        let written = file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writer handed to the fmt layer; forwards to the shared rotating file
+struct FileWriter;
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match LOG_FILE_WRITER.get().and_then(|file| file.lock().ok()) {
+            Some(mut file) => file.write(buf),
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match LOG_FILE_WRITER.get().and_then(|file| file.lock().ok()) {
+            Some(mut file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+// MARK: - Recent Logs
+
+/// A log line kept in memory
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct LogEntry {
+    /// Unix time in milliseconds
+    pub timestamp: u64,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+    /// ID of the HTTP request being handled, if any
+    pub request_id: Option<u64>,
+}
+
+/// Collects an event's message and fields into one line
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+}
+
+/// Request ID stored on `request` spans
+struct RequestId(u64);
+
+struct RequestIdVisitor(Option<u64>);
+
+impl Visit for RequestIdVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        if field.name() == "id" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+/// Layer that keeps the most recent events in `RECENT`
+struct RecentLogs;
+
+impl<S> Layer<S> for RecentLogs
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "request" {
+            return;
+        }
+        let mut visitor = RequestIdVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(request_id), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(RequestId(request_id));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let request_id = ctx.event_scope(event).and_then(|scope| {
+            scope
+                .from_root()
+                .find_map(|span| span.extensions().get::<RequestId>().map(|id| id.0))
+        });
+
+        let entry = LogEntry {
+            timestamp: unix_millis(),
+            level: event.metadata().level().to_string(),
+            target: event.metadata().target().to_string(),
+            message: visitor.message + &visitor.fields,
+            request_id,
+        };
+
+        if let Ok(mut recent) = RECENT.lock() {
+            if recent.len() == RECENT_CAPACITY {
+                recent.pop_front();
+            }
+            recent.push_back(entry);
+        }
+    }
+}
+
+/// Recent entries newer than `since` (Unix ms) at `level` or more severe
+pub fn recent_logs(since: Option<u64>, level: Option<Level>) -> Vec<LogEntry> {
+    let Ok(recent) = RECENT.lock() else {
+        return Vec::new();
+    };
+
+    recent
+        .iter()
+        .filter(|entry| since.is_none_or(|since| entry.timestamp > since))
+        .filter(|entry| match (level, entry.level.parse::<Level>()) {
+            // Level orders TRACE > ... > ERROR, so "at least as severe" is <=
+            (Some(level), Ok(entry_level)) => entry_level <= level,
+            _ => true,
+        })
+        .cloned()
+        .collect()
+}
+
+// MARK: - Setup
+
+/// Next ID for a `request` span
+pub fn next_request_id() -> u64 {
+    NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Install the global subscriber: stdout, the rotating log file (if the log
+/// directory is writable) and the in-memory buffer, filtered at the
+/// configured level
+pub fn init(config: &Config) {
+    let level = config.log_level();
+
+    let file_error = match config::app_log_dir().map(RotatingFile::open) {
+        Some(Ok(file)) => {
+            let _ = LOG_FILE_WRITER.set(Mutex::new(file));
+            None
+        }
+        Some(Err(e)) => Some(e.to_string()),
+        None => Some("could not determine log directory".to_string()),
+    };
+
+    tracing_subscriber::registry()
+        .with(fmt::layer())
+        .with(fmt::layer().with_ansi(false).with_writer(|| FileWriter))
+        .with(RecentLogs)
+        .with(LevelFilter::from_level(level))
+        .init();
+
+    if let Some(e) = file_error {
+        tracing::warn!("File logging disabled: {}", e);
+    }
+}
+
+/// Flush the log file
+pub fn flush() {
+    let _ = FileWriter.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent_logs_filter_by_time_and_level() {
+        let entry = |timestamp, level: &str| LogEntry {
+            timestamp,
+            level: level.to_string(),
+            target: "test".to_string(),
+            message: String::new(),
+            request_id: None,
+        };
+        {
+            let mut recent = RECENT.lock().unwrap();
+            recent.clear();
+            recent.extend([entry(1, "INFO"), entry(2, "WARN"), entry(3, "DEBUG"), entry(4, "ERROR")]);
+        }
+
+        let timestamps = |entries: Vec<LogEntry>| -> Vec<u64> {
+            entries.iter().map(|entry| entry.timestamp).collect()
+        };
+        assert_eq!(timestamps(recent_logs(None, None)), vec![1, 2, 3, 4]);
+        assert_eq!(timestamps(recent_logs(Some(2), None)), vec![3, 4]);
+        assert_eq!(timestamps(recent_logs(None, Some(Level::WARN))), vec![2, 4]);
+        assert_eq!(timestamps(recent_logs(Some(1), Some(Level::INFO))), vec![2, 4]);
+    }
+}
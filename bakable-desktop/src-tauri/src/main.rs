@@ -1,60 +1,46 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod api;
 mod capture;
+mod config;
+mod daemon;
+mod events;
+mod hot_reload;
+mod logging;
+mod mcp;
+mod metrics;
+mod openapi;
 mod plugin;
+mod plugin_manager;
+mod project;
+mod rbxm;
 mod server;
+mod shutdown;
 mod speech;
+mod state;
+#[cfg(all(target_os = "linux", feature = "whisper"))]
+mod whisper;
 
 use tauri::{
     CustomMenuItem, Manager, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem,
-    Window, PhysicalPosition, PhysicalSize, AppHandle,
+    SystemTraySubmenu, Window, PhysicalPosition, PhysicalSize, AppHandle,
 };
 use tracing::{info, error};
 use tracing_subscriber;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
-// Swift FFI for window bounds (fast!)
-#[cfg(target_os = "macos")]
-extern "C" {
-    fn get_roblox_studio_window_bounds(
-        out_x: *mut i32,
-        out_y: *mut i32,
-        out_w: *mut i32,
-        out_h: *mut i32,
-    ) -> bool;
-}
-
-static SNAP_ENABLED: AtomicBool = AtomicBool::new(false);
-
-/// Get Roblox Studio window bounds via Swift FFI (fast!)
-#[cfg(target_os = "macos")]
-fn get_studio_window_bounds() -> Option<(i32, i32, i32, i32)> {
-    let mut x: i32 = 0;
-    let mut y: i32 = 0;
-    let mut w: i32 = 0;
-    let mut h: i32 = 0;
-
-    let found = unsafe {
-        get_roblox_studio_window_bounds(&mut x, &mut y, &mut w, &mut h)
-    };
-
-    if found {
-        Some((x, y, w, h))
-    } else {
-        None
-    }
-}
+/// Whether the helper window follows Studio around
+pub(crate) static SNAP_ENABLED: AtomicBool = AtomicBool::new(false);
 
-#[cfg(not(target_os = "macos"))]
-fn get_studio_window_bounds() -> Option<(i32, i32, i32, i32)> {
-    None
-}
+/// While snap is off, the monitor only probes for Studio every this many ticks
+const STUDIO_PROBE_TICKS: u32 = 60;
 
 /// Position window to the right of Studio
 fn position_next_to_studio(window: &Window) -> Result<(), String> {
-    let bounds = get_studio_window_bounds()
+    let bounds = capture::get_studio_window_bounds()
         .ok_or_else(|| "Roblox Studio not found".to_string())?;
 
     let (studio_x, studio_y, studio_w, studio_h) = bounds;
@@ -78,6 +64,7 @@ async fn snap_to_studio(window: Window) -> Result<String, String> {
     let was_enabled = SNAP_ENABLED.load(Ordering::SeqCst);
     let now_enabled = !was_enabled;
     SNAP_ENABLED.store(now_enabled, Ordering::SeqCst);
+    events::publish(events::Event::SnapChanged { enabled: now_enabled });
 
     if now_enabled {
         info!("Snap to Studio enabled");
@@ -94,26 +81,279 @@ async fn get_snap_status() -> bool {
     SNAP_ENABLED.load(Ordering::SeqCst)
 }
 
-/// Start background task to keep window snapped to Studio
+/// Port, socket and token of the helper server, once it's listening
+#[tauri::command]
+async fn get_helper_connection() -> Result<config::Discovery, String> {
+    config::Discovery::read().ok_or_else(|| "Helper server is not listening yet".to_string())
+}
+
+#[tauri::command]
+async fn get_daemon_state() -> daemon::DaemonState {
+    daemon::current_state()
+}
+
+#[tauri::command]
+async fn get_plugin_status() -> plugin::PluginStatus {
+    plugin::plugin_status()
+}
+
+#[tauri::command]
+async fn set_plugin_channel(app: AppHandle, channel: plugin::PluginChannel) -> Result<plugin::PluginStatus, String> {
+    plugin::switch_channel(channel)?;
+    update_plugin_tray(&app);
+    Ok(plugin::plugin_status())
+}
+
+#[tauri::command]
+async fn uninstall_plugin(app: AppHandle) -> Result<plugin::PluginStatus, String> {
+    plugin::uninstall_plugin()?;
+    update_plugin_tray(&app);
+    Ok(plugin::plugin_status())
+}
+
+#[tauri::command]
+async fn get_plugin_path() -> Option<PathBuf> {
+    plugin::get_plugin_path()
+}
+
+#[tauri::command]
+async fn open_plugins_folder() -> Result<PathBuf, String> {
+    plugin::open_plugins_dir()
+}
+
+#[tauri::command]
+async fn remove_duplicate_plugins() -> Result<Vec<PathBuf>, String> {
+    plugin::remove_duplicates()
+}
+
+#[tauri::command]
+async fn get_plugin_diff() -> plugin::PluginDiff {
+    plugin::plugin_diff()
+}
+
+#[tauri::command]
+async fn get_plugins_dirs() -> plugin::PluginsDirResolution {
+    plugin::resolve_plugins_dir(&config::Config::load())
+}
+
+/// Run a plugin manager call off the async runtime
+async fn with_plugins<T: Send + 'static>(
+    state: tauri::State<'_, state::AppState>,
+    call: impl FnOnce(&dyn state::PluginManager) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let plugins = state.plugins.clone();
+    tokio::task::spawn_blocking(move || call(plugins.as_ref()))
+        .await
+        .map_err(|e| format!("Plugin task failed: {}", e))?
+}
+
+#[tauri::command]
+async fn list_plugins(
+    state: tauri::State<'_, state::AppState>,
+) -> Result<Vec<plugin_manager::ManagedPlugin>, String> {
+    with_plugins(state, |plugins| plugins.list()).await
+}
+
+#[tauri::command]
+async fn disable_plugin(
+    state: tauri::State<'_, state::AppState>,
+    name: String,
+) -> Result<plugin_manager::ManagedPlugin, String> {
+    with_plugins(state, move |plugins| plugins.set_enabled(&name, false)).await
+}
+
+#[tauri::command]
+async fn enable_plugin(
+    state: tauri::State<'_, state::AppState>,
+    name: String,
+) -> Result<plugin_manager::ManagedPlugin, String> {
+    with_plugins(state, move |plugins| plugins.set_enabled(&name, true)).await
+}
+
+#[tauri::command]
+async fn import_plugin(
+    state: tauri::State<'_, state::AppState>,
+    path: PathBuf,
+) -> Result<plugin_manager::ManagedPlugin, String> {
+    with_plugins(state, move |plugins| plugins.import(&path)).await
+}
+
+#[tauri::command]
+async fn get_recent_logs(
+    since: Option<u64>,
+    level: Option<String>,
+) -> Result<Vec<logging::LogEntry>, String> {
+    let level = level
+        .map(|level| level.parse().map_err(|_| format!("Invalid log level: {}", level)))
+        .transpose()?;
+    Ok(logging::recent_logs(since, level))
+}
+
+/// Show the installed plugin version and channel, and only offer a rollback
+/// when there is a previous plugin to restore
+fn update_plugin_tray(app: &AppHandle) {
+    let status = plugin::plugin_status();
+    let tray = app.tray_handle();
+    let _ = tray.get_item("plugin_status").set_title(status.label());
+    let _ = tray.get_item("rollback").set_enabled(status.rollback_available);
+    for channel in plugin::PluginChannel::ALL {
+        let _ = tray.get_item(channel.menu_id()).set_selected(channel == status.channel);
+    }
+}
+
+/// What to do for a new plugin to take effect
+fn studio_hint(studio_running: bool) -> &'static str {
+    match studio_running {
+        true => "Restart Roblox Studio to use it.",
+        false => "Open Roblox Studio to use it.",
+    }
+}
+
+/// Ask before deleting old or duplicate plugin files that an install found
+fn offer_duplicate_removal(app: &AppHandle, duplicates: &[PathBuf]) {
+    if duplicates.is_empty() {
+        return;
+    }
+    let names: Vec<String> = duplicates
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+    info!("Duplicate plugins found: {}", names.join(", "));
+
+    let identifier = app.config().tauri.bundle.identifier.clone();
+    tauri::api::dialog::ask(
+        None::<&Window>,
+        "Remove old Bakable plugins?",
+        format!(
+            "Roblox Studio will load these alongside the current plugin:\n\n{}\n\nRemove them?",
+            names.join("\n")
+        ),
+        move |remove| {
+            if !remove {
+                return;
+            }
+            let body = match plugin::remove_duplicates() {
+                Ok(removed) => format!(
+                    "Removed {} old plugin file(s). {}",
+                    removed.len(),
+                    studio_hint(capture::is_studio_running())
+                ),
+                Err(e) => {
+                    error!("Failed to remove duplicate plugins: {}", e);
+                    format!("Failed to remove old plugins: {}", e)
+                }
+            };
+            let _ = tauri::api::notification::Notification::new(&identifier)
+                .title("Bakable")
+                .body(body)
+                .show();
+        },
+    );
+}
+
+/// Ask before removing the plugin from the tray. Bakable installs it again
+/// the next time it starts.
+fn confirm_uninstall(app: &AppHandle) {
+    let handle = app.clone();
+    tauri::api::dialog::ask(
+        None::<&Window>,
+        "Uninstall the Bakable plugin?",
+        "Roblox Studio will no longer load Bakable until it is reinstalled, from the tray or when Bakable next starts.",
+        move |uninstall| {
+            if !uninstall {
+                return;
+            }
+            let identifier = handle.config().tauri.bundle.identifier.clone();
+            match plugin::uninstall_plugin() {
+                Ok(removed) => {
+                    let body = match (removed, capture::is_studio_running()) {
+                        (false, _) => "The plugin was not installed.",
+                        (true, true) => "Plugin uninstalled. Restart Roblox Studio to unload it.",
+                        (true, false) => "Plugin uninstalled.",
+                    };
+                    let _ = tauri::api::notification::Notification::new(&identifier)
+                        .title("Bakable")
+                        .body(body)
+                        .show();
+                }
+                Err(e) => {
+                    error!("Failed to uninstall plugin: {}", e);
+                    let _ = tauri::api::notification::Notification::new(&identifier)
+                        .title("Bakable Error")
+                        .body(&format!("Failed to uninstall plugin: {}", e))
+                        .show();
+                }
+            }
+            update_plugin_tray(&handle);
+        },
+    );
+}
+
+/// Install the chosen channel's plugin from the tray
+fn switch_plugin_channel(app: &AppHandle, channel: plugin::PluginChannel) {
+    info!("Plugin channel {} requested", channel.name());
+    match plugin::switch_channel(channel) {
+        Ok(report) => {
+            let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+                .title("Bakable")
+                .body(&format!("Switched to the {} plugin. {}", channel.name(), studio_hint(report.studio_running)))
+                .show();
+            offer_duplicate_removal(app, &report.duplicates);
+        }
+        Err(e) => {
+            error!("Failed to switch plugin channel: {}", e);
+            let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+                .title("Bakable Error")
+                .body(&format!("Failed to switch to the {} plugin: {}", channel.name(), e))
+                .show();
+        }
+    }
+    update_plugin_tray(app);
+}
+
+/// Start background task to keep window snapped to Studio and publish
+/// Studio window found/lost events
 fn start_snap_monitor(handle: AppHandle) {
     tauri::async_runtime::spawn(async move {
         let mut last_bounds: Option<(i32, i32, i32, i32)> = None;
+        let mut studio_found = false;
+        let mut ticks: u32 = 0;
 
-        loop {
+        while !shutdown::requested() {
             tokio::time::sleep(Duration::from_millis(8)).await; // ~120fps
+            ticks = ticks.wrapping_add(1);
 
-            if !SNAP_ENABLED.load(Ordering::SeqCst) {
+            let snap_enabled = SNAP_ENABLED.load(Ordering::SeqCst);
+            if !snap_enabled {
                 last_bounds = None;
+                if !ticks.is_multiple_of(STUDIO_PROBE_TICKS) {
+                    continue;
+                }
+            }
+
+            let bounds = capture::get_studio_window_bounds();
+            if bounds.is_some() != studio_found {
+                studio_found = bounds.is_some();
+                events::publish(match bounds {
+                    Some((x, y, width, height)) => {
+                        events::Event::StudioWindowFound { x, y, width, height }
+                    }
+                    None => events::Event::StudioWindowLost,
+                });
+            }
+
+            if !snap_enabled {
                 continue;
             }
 
-            if let Some(window) = handle.get_window("main") {
-                if let Some(bounds) = get_studio_window_bounds() {
-                    // Only update if bounds changed
-                    if last_bounds != Some(bounds) {
-                        let _ = position_next_to_studio(&window);
-                        last_bounds = Some(bounds);
+            if let (Some(window), Some(bounds)) = (handle.get_window("main"), bounds) {
+                // Only update if bounds changed
+                if last_bounds != Some(bounds) {
+                    if position_next_to_studio(&window).is_ok() {
+                        metrics::SNAP_REPOSITIONS.inc(&[]);
                     }
+                    last_bounds = Some(bounds);
                 }
             }
         }
@@ -121,20 +361,48 @@ fn start_snap_monitor(handle: AppHandle) {
 }
 
 fn main() {
+    // `--mcp` runs only the MCP server on stdio, without the window or tray
+    if std::env::args().any(|arg| arg == "--mcp") {
+        // stdout carries protocol messages, so logs go to stderr
+        tracing_subscriber::fmt().with_writer(std::io::stderr).init();
+
+        let runtime = tokio::runtime::Runtime::new().expect("failed to start tokio runtime");
+        if let Err(e) = runtime.block_on(mcp::serve_stdio()) {
+            error!("MCP server failed: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Initialize logging
-    tracing_subscriber::fmt::init();
+    let config = config::Config::load();
+    logging::init(&config);
 
     // Build system tray menu
     let quit = CustomMenuItem::new("quit".to_string(), "Quit Bakable");
     let status = CustomMenuItem::new("status".to_string(), "Status: Starting...").disabled();
+    let daemon_status = CustomMenuItem::new("daemon_status".to_string(), "Daemon: Starting...").disabled();
+    let plugin_status = CustomMenuItem::new("plugin_status".to_string(), "Plugin: Checking...").disabled();
     let reinstall = CustomMenuItem::new("reinstall".to_string(), "Reinstall Plugin");
+    let rollback = CustomMenuItem::new("rollback".to_string(), "Restore Previous Plugin");
+    let uninstall = CustomMenuItem::new("uninstall".to_string(), "Uninstall Plugin");
+    let selected_channel = plugin::selected_channel();
+    let channels = plugin::PluginChannel::ALL.into_iter().fold(SystemTrayMenu::new(), |menu, channel| {
+        let item = CustomMenuItem::new(channel.menu_id().to_string(), channel.title());
+        menu.add_item(if channel == selected_channel { item.selected() } else { item })
+    });
     let check_update = CustomMenuItem::new("check_update".to_string(), "Check for Updates");
     let open_plugins = CustomMenuItem::new("open_plugins".to_string(), "Open Plugins Folder");
 
     let tray_menu = SystemTrayMenu::new()
         .add_item(status)
+        .add_item(daemon_status)
         .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(plugin_status)
         .add_item(reinstall)
+        .add_item(rollback)
+        .add_item(uninstall)
+        .add_submenu(SystemTraySubmenu::new("Plugin Channel", channels))
         .add_item(open_plugins)
         .add_native_item(SystemTrayMenuItem::Separator)
         .add_item(check_update)
@@ -143,24 +411,52 @@ fn main() {
 
     let system_tray = SystemTray::new().with_menu(tray_menu);
 
+    // Shared with the HTTP server, the Tauri commands and shutdown
+    let state = state::AppState::system();
+
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![snap_to_studio, get_snap_status])
+        .manage(state)
+        .invoke_handler(tauri::generate_handler![
+            snap_to_studio,
+            get_snap_status,
+            get_helper_connection,
+            get_daemon_state,
+            get_plugin_status,
+            set_plugin_channel,
+            uninstall_plugin,
+            get_plugin_path,
+            open_plugins_folder,
+            remove_duplicate_plugins,
+            get_plugin_diff,
+            get_plugins_dirs,
+            list_plugins,
+            disable_plugin,
+            enable_plugin,
+            import_plugin,
+            get_recent_logs
+        ])
         .system_tray(system_tray)
         .on_system_tray_event(|app, event| match event {
             SystemTrayEvent::MenuItemClick { id, .. } => {
                 match id.as_str() {
                     "quit" => {
                         info!("Quit requested");
-                        std::process::exit(0);
+                        let handle = app.clone();
+                        let speech = app.state::<state::AppState>().speech.clone();
+                        tauri::async_runtime::spawn(async move {
+                            shutdown::run(speech).await;
+                            handle.exit(0);
+                        });
                     }
                     "reinstall" => {
                         info!("Reinstall plugin requested");
-                        match plugin::install_plugin() {
-                            Ok(_) => {
+                        match plugin::reinstall_plugin() {
+                            Ok(report) => {
                                 let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
                                     .title("Bakable")
-                                    .body("Plugin reinstalled successfully! Restart Roblox Studio to use it.")
+                                    .body(&format!("Plugin reinstalled successfully! {}", studio_hint(report.studio_running)))
                                     .show();
+                                offer_duplicate_removal(app, &report.duplicates);
                             }
                             Err(e) => {
                                 error!("Failed to reinstall plugin: {}", e);
@@ -170,13 +466,38 @@ fn main() {
                                     .show();
                             }
                         }
+                        update_plugin_tray(app);
+                    }
+                    "rollback" => {
+                        info!("Plugin rollback requested");
+                        match plugin::rollback_plugin() {
+                            Ok(()) => {
+                                let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+                                    .title("Bakable")
+                                    .body(&format!("Previous plugin restored. {}", studio_hint(capture::is_studio_running())))
+                                    .show();
+                            }
+                            Err(e) => {
+                                error!("Failed to restore previous plugin: {}", e);
+                                let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+                                    .title("Bakable Error")
+                                    .body(&format!("Failed to restore previous plugin: {}", e))
+                                    .show();
+                            }
+                        }
+                        update_plugin_tray(app);
+                    }
+                    "uninstall" => {
+                        info!("Uninstall plugin requested");
+                        confirm_uninstall(app);
                     }
                     "open_plugins" => {
-                        if let Some(home) = dirs::home_dir() {
-                            let plugins_path = home.join("Documents/Roblox/Plugins");
-                            let _ = std::process::Command::new("open")
-                                .arg(plugins_path)
-                                .spawn();
+                        if let Err(e) = plugin::open_plugins_dir() {
+                            error!("Failed to open plugins folder: {}", e);
+                            let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+                                .title("Bakable Error")
+                                .body(&format!("Failed to open plugins folder: {}", e))
+                                .show();
                         }
                     }
                     "check_update" => {
@@ -184,46 +505,129 @@ fn main() {
                         // Tauri updater handles this automatically
                         app.trigger_global("tauri://update", None);
                     }
-                    _ => {}
+                    id => {
+                        if let Some(channel) = plugin::PluginChannel::from_menu_id(id) {
+                            switch_plugin_channel(app, channel);
+                        }
+                    }
                 }
             }
             _ => {}
         })
-        .setup(|app| {
+        .setup(move |app| {
             info!("Bakable Desktop starting up...");
 
+            let state = app.state::<state::AppState>().inner().clone();
+
+            let plugins_dir = plugin::resolve_plugins_dir(&config);
+            match &plugins_dir.chosen {
+                Some(dir) => info!("Plugins directory: {:?} ({})", dir, plugins_dir.reason),
+                None => error!("No plugins directory: {}", plugins_dir.reason),
+            }
+
             // Install plugin on first run
-            match plugin::install_plugin() {
-                Ok(installed) => {
-                    if installed {
+            match state.plugin.install() {
+                Ok(report) => {
+                    if report.installed {
                         info!("Plugin installed successfully");
+                        let body = match report.restart_needed() {
+                            true => "Plugin updated! Restart Roblox Studio to use it.",
+                            false => "Plugin installed! Open Roblox Studio to use Bakable.",
+                        };
                         let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
                             .title("Bakable")
-                            .body("Plugin installed! Open Roblox Studio to use Bakable.")
+                            .body(body)
                             .show();
                     } else {
                         info!("Plugin already installed");
                     }
+                    offer_duplicate_removal(&app.handle(), &report.duplicates);
                 }
                 Err(e) => {
                     error!("Failed to install plugin: {}", e);
+                    let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+                        .title("Bakable Error")
+                        .body(&format!("Failed to install plugin: {}", e))
+                        .show();
                 }
             }
+            update_plugin_tray(&app.handle());
 
             // Start HTTP server for screenshot capture
+            let cors = server::CorsConfig::from_env()
+                .with_origins(config.allowed_origins.iter().map(String::as_str));
+            let daemon_config = config.daemon.clone();
+            let plugin_dev = config.plugin_dev.clone();
+            let speech = state.speech.clone();
             let handle = app.handle();
             tauri::async_runtime::spawn(async move {
-                if let Err(e) = server::start_capture_server(handle).await {
-                    error!("Failed to start capture server: {}", e);
+                // Shutdown waits for the server to drain in-flight requests
+                let _running = shutdown::track();
+                let status = handle.tray_handle().get_item("status");
+
+                let listeners = match server::Listeners::bind(&config).await {
+                    Ok(listeners) => listeners,
+                    Err(e) => {
+                        error!("Failed to bind capture server: {}", e);
+                        let _ = status.set_title(format!("Status: Error ({})", e));
+                        return;
+                    }
+                };
+                let port = listeners.port();
+                let socket = listeners.socket();
+
+                let discovery = config::Discovery::new(port, socket.clone(), state.token.clone());
+                if let Err(e) = discovery.write() {
+                    error!("Failed to write discovery file: {}", e);
+                }
+                let _ = status.set_title(match (port, socket) {
+                    (Some(port), Some(_)) => format!("Status: Running (port {} + socket)", port),
+                    (Some(port), None) => format!("Status: Running (port {})", port),
+                    (None, _) => "Status: Running (socket)".to_string(),
+                });
+
+                if let Err(e) = server::start_capture_server(handle, listeners, state, cors).await {
+                    error!("Capture server stopped: {}", e);
+                    let _ = status.set_title(format!("Status: Stopped ({})", e));
                 }
             });
 
+            // Spawn and supervise the Node daemon
+            let daemon_status = app.tray_handle().get_item("daemon_status");
+            tauri::async_runtime::spawn(daemon::run(daemon_config, move |state| {
+                let _ = daemon_status.set_title(state.label());
+            }));
+
+            // Rebuild and reinstall the plugin as its sources change (dev mode)
+            let identifier = app.config().tauri.bundle.identifier.clone();
+            let handle = app.handle();
+            tauri::async_runtime::spawn(hot_reload::run(
+                plugin_dev,
+                move |title, body| {
+                    let _ = tauri::api::notification::Notification::new(&identifier)
+                        .title(title)
+                        .body(body)
+                        .show();
+                },
+                move || update_plugin_tray(&handle),
+            ));
+
             // Start snap-to-studio monitor
             start_snap_monitor(app.handle());
 
-            // Update tray status
-            let tray_handle = app.tray_handle();
-            let _ = tray_handle.get_item("status").set_title("Status: Running (port 4850)");
+            // Publish speech and permission changes to /ws clients
+            tauri::async_runtime::spawn(speech::watch_events(speech));
+            tauri::async_runtime::spawn(capture::watch_permission());
+
+            // SIGINT/SIGTERM take the same path as quitting from the tray
+            let handle = app.handle();
+            let speech = app.state::<state::AppState>().speech.clone();
+            tauri::async_runtime::spawn(async move {
+                shutdown::os_signal().await;
+                info!("Termination signal received");
+                shutdown::run(speech).await;
+                handle.exit(0);
+            });
 
             info!("Bakable Desktop ready");
             Ok(())
@@ -0,0 +1,388 @@
+//! MCP server exposing the helper's capabilities as tools
+//!
+//! Speaks JSON-RPC 2.0 over two transports:
+//! - stdio, when the binary is started with `--mcp` (no window or tray)
+//! - streamable HTTP, via `POST /mcp` on the helper server
+//!
+//! Tool input schemas are generated from the same request structs the HTTP
+//! routes deserialize, so both surfaces stay in sync.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use schemars::{schema_for, JsonSchema};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tracing::{info, warn};
+
+use crate::api::ApiError;
+use crate::metrics;
+use crate::server::{self, CaptureQuery, SpeakRequest};
+use crate::state::AppState;
+
+/// Protocol revisions we can speak, newest first
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Input of tools that take no arguments
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+struct NoParams {}
+
+/// A tool the helper exposes
+struct Tool {
+    name: &'static str,
+    description: &'static str,
+    input_schema: fn() -> Value,
+}
+
+fn schema<T: JsonSchema>() -> Value {
+    serde_json::to_value(schema_for!(T)).unwrap_or_else(|_| json!({ "type": "object" }))
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        name: "helper.captureViewport",
+        description: "Capture a screenshot of the Roblox Studio window. Returns a PNG image, or JSON with base64 data when format is 'base64'.",
+        input_schema: schema::<CaptureQuery>,
+    },
+    Tool {
+        name: "helper.getWindowInfo",
+        description: "Get whether a Roblox Studio window is open, its window ID and its screen bounds.",
+        input_schema: schema::<NoParams>,
+    },
+    Tool {
+        name: "helper.getPermission",
+        description: "Check screen capture permission, requesting it from the user if not yet granted.",
+        input_schema: schema::<NoParams>,
+    },
+    Tool {
+        name: "helper.speech.status",
+        description: "Get speech recognition and text-to-speech status.",
+        input_schema: schema::<NoParams>,
+    },
+    Tool {
+        name: "helper.speech.listen",
+        description: "Start speech recognition from the microphone.",
+        input_schema: schema::<NoParams>,
+    },
+    Tool {
+        name: "helper.speech.stop",
+        description: "Stop speech recognition.",
+        input_schema: schema::<NoParams>,
+    },
+    Tool {
+        name: "helper.speech.getTranscription",
+        description: "Get the current speech recognition transcription.",
+        input_schema: schema::<NoParams>,
+    },
+    Tool {
+        name: "helper.speech.speak",
+        description: "Speak text aloud with text-to-speech.",
+        input_schema: schema::<SpeakRequest>,
+    },
+    Tool {
+        name: "helper.speech.silence",
+        description: "Stop any text-to-speech in progress.",
+        input_schema: schema::<NoParams>,
+    },
+    Tool {
+        name: "helper.plugin.status",
+        description: "Get whether the Bakable Studio plugin is installed, where, and whether an update is available.",
+        input_schema: schema::<NoParams>,
+    },
+];
+
+#[derive(Debug, Serialize)]
+struct WindowInfo {
+    found: bool,
+    window_id: Option<i64>,
+    x: Option<i32>,
+    y: Option<i32>,
+    width: Option<i32>,
+    height: Option<i32>,
+}
+
+/// Result of a tool call, before it is wrapped in MCP content
+enum ToolOutput {
+    Json(Value),
+    Image { data: String, mime_type: &'static str },
+}
+
+fn to_json(value: impl Serialize) -> Result<ToolOutput, String> {
+    serde_json::to_value(value)
+        .map(ToolOutput::Json)
+        .map_err(|e| e.to_string())
+}
+
+/// Tool output from an HTTP handler's result; API errors become tool errors
+fn from_handler<T: Serialize>(result: Result<Json<T>, ApiError>) -> Result<ToolOutput, String> {
+    match result {
+        Ok(Json(value)) => to_json(value),
+        Err(e) => Err(e.message),
+    }
+}
+
+fn parse_args<T: DeserializeOwned>(args: Value) -> Result<T, String> {
+    serde_json::from_value(args).map_err(|e| format!("Invalid arguments: {}", e))
+}
+
+async fn call_tool(state: &AppState, name: &str, args: Value) -> Result<ToolOutput, String> {
+    match name {
+        "helper.captureViewport" => capture_viewport(state, parse_args(args)?).await,
+        "helper.getWindowInfo" => {
+            let bounds = state.capture.window_bounds();
+            to_json(WindowInfo {
+                found: bounds.is_some(),
+                window_id: state.capture.window_id(),
+                x: bounds.map(|b| b.0),
+                y: bounds.map(|b| b.1),
+                width: bounds.map(|b| b.2),
+                height: bounds.map(|b| b.3),
+            })
+        }
+        "helper.getPermission" => to_json(server::permission_handler(State(state.clone())).await.0),
+        "helper.speech.status" => to_json(server::speech_status_handler(State(state.clone())).await.0),
+        "helper.speech.listen" => from_handler(server::start_listen_handler(State(state.clone())).await),
+        "helper.speech.stop" => to_json(server::stop_listen_handler(State(state.clone())).await.0),
+        "helper.speech.getTranscription" => {
+            to_json(server::transcription_handler(State(state.clone())).await.0)
+        }
+        "helper.speech.speak" => {
+            let request: SpeakRequest = parse_args(args)?;
+            from_handler(server::speak_handler(State(state.clone()), Ok(Json(request))).await)
+        }
+        "helper.speech.silence" => to_json(server::silence_handler(State(state.clone())).await.0),
+        "helper.plugin.status" => to_json(state.plugin.status()),
+        _ => Err(format!("Unknown tool: {}", name)),
+    }
+}
+
+async fn capture_viewport(state: &AppState, params: CaptureQuery) -> Result<ToolOutput, String> {
+    use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+
+    if !state.capture.has_permission() {
+        metrics::CAPTURE_FAILURES.inc(&[("code", "PERMISSION_DENIED")]);
+        return Err("Screen capture permission not granted. Call helper.getPermission to request.".to_string());
+    }
+
+    let capture = state.capture.clone();
+    let png_data = tokio::task::spawn_blocking(move || capture.capture_viewport())
+        .await
+        .map_err(|e| format!("Capture task failed: {}", e))?
+        .ok_or_else(|| "Failed to capture Roblox Studio. Is it running?".to_string())?;
+    let data = BASE64.encode(&png_data);
+
+    if params.format.as_deref() == Some("base64") {
+        return Ok(ToolOutput::Json(json!({ "base64": data, "media_type": "image/png" })));
+    }
+
+    Ok(ToolOutput::Image { data, mime_type: "image/png" })
+}
+
+fn success(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn failure(id: Value, code: i64, message: impl Into<String>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message.into() },
+    })
+}
+
+/// Handle one JSON-RPC message. Returns None for notifications.
+pub async fn handle_message(state: &AppState, message: Value) -> Option<Value> {
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        return Some(failure(Value::Null, INVALID_REQUEST, "Expected a JSON-RPC request"));
+    };
+
+    // Requests carry an id; notifications don't and get no response
+    let id = message.get("id").cloned()?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    Some(match method {
+        "initialize" => {
+            let requested = params.get("protocolVersion").and_then(Value::as_str);
+            let version = requested
+                .filter(|v| PROTOCOL_VERSIONS.contains(v))
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            success(
+                id,
+                json!({
+                    "protocolVersion": version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": {
+                        "name": "bakable-desktop",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }),
+            )
+        }
+        "ping" => success(id, json!({})),
+        "tools/list" => {
+            let tools: Vec<Value> = TOOLS
+                .iter()
+                .map(|tool| {
+                    json!({
+                        "name": tool.name,
+                        "description": tool.description,
+                        "inputSchema": (tool.input_schema)(),
+                    })
+                })
+                .collect();
+            success(id, json!({ "tools": tools }))
+        }
+        "tools/call" => {
+            let Some(name) = params.get("name").and_then(Value::as_str) else {
+                return Some(failure(id, INVALID_PARAMS, "Missing tool name"));
+            };
+            if !TOOLS.iter().any(|tool| tool.name == name) {
+                return Some(failure(id, INVALID_PARAMS, format!("Unknown tool: {}", name)));
+            }
+
+            let args = match params.get("arguments") {
+                Some(Value::Null) | None => json!({}),
+                Some(args) => args.clone(),
+            };
+
+            let result = match call_tool(state, name, args).await {
+                Ok(ToolOutput::Json(value)) => json!({
+                    "content": [{
+                        "type": "text",
+                        "text": serde_json::to_string_pretty(&value).unwrap_or_default(),
+                    }],
+                    "structuredContent": value,
+                }),
+                Ok(ToolOutput::Image { data, mime_type }) => json!({
+                    "content": [{ "type": "image", "data": data, "mimeType": mime_type }],
+                }),
+                Err(message) => json!({
+                    "content": [{ "type": "text", "text": format!("Error: {}", message) }],
+                    "isError": true,
+                }),
+            };
+            success(id, result)
+        }
+        _ => failure(id, METHOD_NOT_FOUND, format!("Method not found: {}", method)),
+    })
+}
+
+/// Streamable HTTP transport: `POST /mcp` with a single JSON-RPC message
+pub async fn http_handler(State(state): State<AppState>, body: String) -> Response {
+    let message = match serde_json::from_str::<Value>(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(failure(Value::Null, PARSE_ERROR, e.to_string())),
+            )
+                .into_response()
+        }
+    };
+
+    match handle_message(&state, message).await {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+/// Stdio transport: newline-delimited JSON-RPC on stdin/stdout until EOF
+pub async fn serve_stdio() -> std::io::Result<()> {
+    info!("Starting MCP server on stdio");
+
+    let state = AppState::system();
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&state, message).await,
+            Err(e) => {
+                warn!("Invalid MCP message: {}", e);
+                Some(failure(Value::Null, PARSE_ERROR, e.to_string()))
+            }
+        };
+
+        if let Some(response) = response {
+            let mut out = serde_json::to_vec(&response)?;
+            out.push(b'\n');
+            stdout.write_all(&out).await?;
+            stdout.flush().await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::fakes::Fakes;
+
+    async fn request(method: &str, params: Value) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        handle_message(&Fakes::default().state(), message).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn initialize_negotiates_protocol_version() {
+        let response = request("initialize", json!({ "protocolVersion": "2025-03-26" })).await;
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+        assert!(response["result"]["capabilities"]["tools"].is_object());
+
+        let response = request("initialize", json!({ "protocolVersion": "1999-01-01" })).await;
+        assert_eq!(response["result"]["protocolVersion"], PROTOCOL_VERSIONS[0]);
+    }
+
+    #[tokio::test]
+    async fn tool_schemas_come_from_request_structs() {
+        let response = request("tools/list", json!({})).await;
+        let tools = response["result"]["tools"].as_array().unwrap();
+        let schema_of = |name: &str| {
+            tools.iter().find(|tool| tool["name"] == name).unwrap()["inputSchema"].clone()
+        };
+
+        let capture = schema_of("helper.captureViewport");
+        assert!(capture["properties"]["format"].is_object());
+        assert!(capture["properties"]["width"].is_null());
+
+        let speak = schema_of("helper.speech.speak");
+        assert_eq!(speak["required"], json!(["text"]));
+    }
+
+    #[tokio::test]
+    async fn notifications_get_no_response() {
+        let message = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(handle_message(&Fakes::default().state(), message).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn unknown_methods_and_tools_are_errors() {
+        let response = request("resources/list", json!({})).await;
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let response = request("tools/call", json!({ "name": "helper.nope" })).await;
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn invalid_tool_arguments_are_tool_errors() {
+        let response = request("tools/call", json!({ "name": "helper.speech.speak", "arguments": {} })).await;
+        assert_eq!(response["result"]["isError"], true);
+    }
+}
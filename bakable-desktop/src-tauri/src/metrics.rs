@@ -0,0 +1,254 @@
+//! Prometheus-style metrics
+//!
+//! A small in-process registry of labelled counters and histograms, rendered
+//! in the Prometheus text exposition format by `GET /metrics`.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Latency buckets in seconds
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Size buckets in bytes (64 KiB to 64 MiB)
+const BYTE_BUCKETS: &[f64] = &[
+    65_536.0,
+    262_144.0,
+    1_048_576.0,
+    4_194_304.0,
+    16_777_216.0,
+    67_108_864.0,
+];
+
+type Labels = Vec<(&'static str, String)>;
+
+fn to_labels(labels: &[(&'static str, &str)]) -> Labels {
+    labels.iter().map(|(k, v)| (*k, v.to_string())).collect()
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_labels(labels: &Labels, extra: Option<(&str, &str)>) -> String {
+    let mut parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+        .collect();
+    if let Some((k, v)) = extra {
+        parts.push(format!("{}=\"{}\"", k, v));
+    }
+
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+/// Monotonic counter, one series per label set
+pub struct Counter {
+    name: &'static str,
+    help: &'static str,
+    values: Mutex<BTreeMap<Labels, u64>>,
+}
+
+impl Counter {
+    const fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Add one to the series with these labels
+    pub fn inc(&self, labels: &[(&'static str, &str)]) {
+        if let Ok(mut values) = self.values.lock() {
+            *values.entry(to_labels(labels)).or_default() += 1;
+        }
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} counter", self.name);
+        if let Ok(values) = self.values.lock() {
+            for (labels, value) in values.iter() {
+                let _ = writeln!(out, "{}{} {}", self.name, format_labels(labels, None), value);
+            }
+        }
+    }
+}
+
+struct HistogramData {
+    /// Non-cumulative count per bucket
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Histogram with fixed buckets, one series per label set
+pub struct Histogram {
+    name: &'static str,
+    help: &'static str,
+    buckets: &'static [f64],
+    values: Mutex<BTreeMap<Labels, HistogramData>>,
+}
+
+impl Histogram {
+    const fn new(name: &'static str, help: &'static str, buckets: &'static [f64]) -> Self {
+        Self {
+            name,
+            help,
+            buckets,
+            values: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Record a value in the series with these labels
+    pub fn observe(&self, labels: &[(&'static str, &str)], value: f64) {
+        let Ok(mut values) = self.values.lock() else { return };
+        let data = values
+            .entry(to_labels(labels))
+            .or_insert_with(|| HistogramData {
+                counts: vec![0; self.buckets.len()],
+                sum: 0.0,
+                count: 0,
+            });
+
+        if let Some(bucket) = self.buckets.iter().position(|bound| value <= *bound) {
+            data.counts[bucket] += 1;
+        }
+        data.sum += value;
+        data.count += 1;
+    }
+
+    /// Record a duration in seconds
+    pub fn observe_duration(&self, labels: &[(&'static str, &str)], duration: Duration) {
+        self.observe(labels, duration.as_secs_f64());
+    }
+
+    fn render(&self, out: &mut String) {
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} histogram", self.name);
+        let Ok(values) = self.values.lock() else { return };
+
+        for (labels, data) in values.iter() {
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(&data.counts) {
+                cumulative += count;
+                let le = bound.to_string();
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{} {}",
+                    self.name,
+                    format_labels(labels, Some(("le", &le))),
+                    cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{} {}",
+                self.name,
+                format_labels(labels, Some(("le", "+Inf"))),
+                data.count
+            );
+            let _ = writeln!(out, "{}_sum{} {}", self.name, format_labels(labels, None), data.sum);
+            let _ = writeln!(out, "{}_count{} {}", self.name, format_labels(labels, None), data.count);
+        }
+    }
+}
+
+// MARK: - Metrics
+
+pub static HTTP_REQUESTS: Counter = Counter::new(
+    "bakable_http_requests_total",
+    "HTTP requests handled, by method, route and status",
+);
+
+pub static HTTP_DURATION: Histogram = Histogram::new(
+    "bakable_http_request_duration_seconds",
+    "HTTP request latency, by method and route",
+    LATENCY_BUCKETS,
+);
+
+pub static CAPTURE_DURATION: Histogram = Histogram::new(
+    "bakable_capture_duration_seconds",
+    "Time taken by successful Studio captures",
+    LATENCY_BUCKETS,
+);
+
+pub static CAPTURE_BYTES: Histogram = Histogram::new(
+    "bakable_capture_bytes",
+    "PNG size of successful Studio captures",
+    BYTE_BUCKETS,
+);
+
+pub static CAPTURE_FAILURES: Counter = Counter::new(
+    "bakable_capture_failures_total",
+    "Failed Studio captures, by error code",
+);
+
+pub static SPEECH_SESSIONS: Counter = Counter::new(
+    "bakable_speech_sessions_total",
+    "Speech sessions, by kind (stt or tts) and result (started or failed)",
+);
+
+pub static SNAP_REPOSITIONS: Counter = Counter::new(
+    "bakable_snap_repositions_total",
+    "Times the snap monitor moved the window next to Studio",
+);
+
+static COUNTERS: &[&Counter] = &[
+    &HTTP_REQUESTS,
+    &CAPTURE_FAILURES,
+    &SPEECH_SESSIONS,
+    &SNAP_REPOSITIONS,
+];
+
+static HISTOGRAMS: &[&Histogram] = &[&HTTP_DURATION, &CAPTURE_DURATION, &CAPTURE_BYTES];
+
+/// Render all metrics in the Prometheus text format
+pub fn render() -> String {
+    let mut out = String::new();
+    for counter in COUNTERS {
+        counter.render(&mut out);
+    }
+    for histogram in HISTOGRAMS {
+        histogram.render(&mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters_and_cumulative_histograms() {
+        let counter = Counter::new("test_total", "Test counter");
+        counter.inc(&[("code", "A\"B")]);
+        counter.inc(&[("code", "A\"B")]);
+
+        let histogram = Histogram::new("test_seconds", "Test histogram", &[0.1, 1.0]);
+        histogram.observe(&[], 0.05);
+        histogram.observe(&[], 0.5);
+        histogram.observe(&[], 5.0);
+
+        let mut out = String::new();
+        counter.render(&mut out);
+        histogram.render(&mut out);
+
+        assert!(out.contains("# TYPE test_total counter\n"));
+        assert!(out.contains("test_total{code=\"A\\\"B\"} 2\n"));
+        assert!(out.contains("test_seconds_bucket{le=\"0.1\"} 1\n"));
+        assert!(out.contains("test_seconds_bucket{le=\"1\"} 2\n"));
+        assert!(out.contains("test_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(out.contains("test_seconds_sum 5.55\n"));
+        assert!(out.contains("test_seconds_count 3\n"));
+    }
+}
@@ -0,0 +1,396 @@
+//! OpenAPI document for the `/v1` API, served at `/v1/openapi.json`
+//!
+//! Schemas are generated from the same structs the handlers serialize and
+//! deserialize, so the document can't drift from the types. The operation
+//! table itself mirrors `server::api_routes`.
+
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use crate::api::ApiError;
+use crate::plugin::{PluginDiff, PluginStatus, PluginsDirResolution};
+use crate::plugin_manager::ManagedPlugin;
+use crate::server::{
+    CaptureBase64Response, CaptureQuery, GenericResponse, HealthQuery, HealthResponse,
+    ImportPluginRequest, LogsQuery, LogsResponse, PermissionResponse, PluginListResponse,
+    PluginNameRequest, SpeakRequest, SpeechStatus, TranscriptionResponse,
+};
+
+type SchemaFn = fn(&mut SchemaGenerator) -> Value;
+
+/// What a successful response contains
+enum Content {
+    Json(SchemaFn),
+    /// PNG by default, JSON when the query asks for base64
+    PngOrJson(SchemaFn),
+    Text,
+    WebSocket,
+}
+
+struct Operation {
+    method: &'static str,
+    path: &'static str,
+    summary: &'static str,
+    query: Option<SchemaFn>,
+    body: Option<SchemaFn>,
+    content: Content,
+    /// Error statuses beyond the 403 every route can return for foreign origins
+    errors: &'static [u16],
+}
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(gen.subschema_for::<T>()).unwrap_or_default()
+}
+
+fn root_schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(gen.root_schema_for::<T>()).unwrap_or_default()
+}
+
+fn json_rpc(_gen: &mut SchemaGenerator) -> Value {
+    json!({ "type": "object", "description": "JSON-RPC 2.0 message (see MCP)" })
+}
+
+const OPERATIONS: &[Operation] = &[
+    Operation {
+        method: "get",
+        path: "/health",
+        summary: "Plugin, Studio, daemon and session health",
+        query: Some(root_schema::<HealthQuery>),
+        body: None,
+        content: Content::Json(schema::<HealthResponse>),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/permission",
+        summary: "Check screen capture permission, requesting it if not granted",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<PermissionResponse>),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/capture",
+        summary: "Capture the Roblox Studio viewport",
+        query: Some(root_schema::<CaptureQuery>),
+        body: None,
+        content: Content::PngOrJson(schema::<CaptureBase64Response>),
+        errors: &[400, 503],
+    },
+    Operation {
+        method: "get",
+        path: "/speech/status",
+        summary: "Speech recognition and text-to-speech status",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<SpeechStatus>),
+        errors: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/speech/listen",
+        summary: "Start speech recognition",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<GenericResponse>),
+        errors: &[503],
+    },
+    Operation {
+        method: "post",
+        path: "/speech/stop",
+        summary: "Stop speech recognition",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<GenericResponse>),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/speech/transcription",
+        summary: "Current transcription",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<TranscriptionResponse>),
+        errors: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/speech/speak",
+        summary: "Speak text aloud",
+        query: None,
+        body: Some(schema::<SpeakRequest>),
+        content: Content::Json(schema::<GenericResponse>),
+        errors: &[400, 415, 422, 503],
+    },
+    Operation {
+        method: "post",
+        path: "/speech/silence",
+        summary: "Stop speaking",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<GenericResponse>),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/plugin/status",
+        summary: "Installed Studio plugin version compared with the bundled one",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<PluginStatus>),
+        errors: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/plugin/uninstall",
+        summary: "Remove the Bakable plugin, from every channel",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<GenericResponse>),
+        errors: &[503],
+    },
+    Operation {
+        method: "get",
+        path: "/plugin/dirs",
+        summary: "Candidate Roblox plugins directories, with the chosen one and why",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<PluginsDirResolution>),
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/plugin/diff",
+        summary: "Instance trees and script hashes of the installed and bundled plugins, and which differ",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<PluginDiff>),
+        errors: &[503],
+    },
+    Operation {
+        method: "get",
+        path: "/plugins",
+        summary: "Every plugin file in the plugins directory and Plugins.disabled",
+        query: None,
+        body: None,
+        content: Content::Json(schema::<PluginListResponse>),
+        errors: &[503],
+    },
+    Operation {
+        method: "post",
+        path: "/plugins/disable",
+        summary: "Move a plugin to Plugins.disabled so Studio stops loading it",
+        query: None,
+        body: Some(schema::<PluginNameRequest>),
+        content: Content::Json(schema::<ManagedPlugin>),
+        errors: &[400, 415, 422],
+    },
+    Operation {
+        method: "post",
+        path: "/plugins/enable",
+        summary: "Move a disabled plugin back into the plugins directory",
+        query: None,
+        body: Some(schema::<PluginNameRequest>),
+        content: Content::Json(schema::<ManagedPlugin>),
+        errors: &[400, 415, 422],
+    },
+    Operation {
+        method: "post",
+        path: "/plugins/import",
+        summary: "Copy a .rbxm, .rbxmx or .lua plugin file into the plugins directory",
+        query: None,
+        body: Some(schema::<ImportPluginRequest>),
+        content: Content::Json(schema::<ManagedPlugin>),
+        errors: &[400, 415, 422],
+    },
+    Operation {
+        method: "get",
+        path: "/ws",
+        summary: "WebSocket stream of helper events; the token may be passed as ?token=",
+        query: None,
+        body: None,
+        content: Content::WebSocket,
+        errors: &[],
+    },
+    Operation {
+        method: "post",
+        path: "/mcp",
+        summary: "MCP streamable HTTP transport",
+        query: None,
+        body: Some(json_rpc),
+        content: Content::Json(json_rpc),
+        errors: &[400],
+    },
+    Operation {
+        method: "get",
+        path: "/metrics",
+        summary: "Prometheus metrics",
+        query: None,
+        body: None,
+        content: Content::Text,
+        errors: &[],
+    },
+    Operation {
+        method: "get",
+        path: "/logs",
+        summary: "Recent helper log entries",
+        query: Some(root_schema::<LogsQuery>),
+        body: None,
+        content: Content::Json(schema::<LogsResponse>),
+        errors: &[400],
+    },
+];
+
+/// Turn a struct's schema into OpenAPI query parameters, one per property
+fn query_parameters(schema: Value) -> Vec<Value> {
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    schema["properties"]
+        .as_object()
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, property)| {
+                    let mut property = property.clone();
+                    let description = property
+                        .as_object_mut()
+                        .and_then(|property| property.remove("description"));
+                    json!({
+                        "name": name,
+                        "in": "query",
+                        "required": required.contains(&name.as_str()),
+                        "description": description,
+                        "schema": property,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn status_description(status: u16) -> &'static str {
+    match status {
+        400 => "Invalid request",
+        401 => "Missing or invalid bearer token",
+        403 => "Permission or origin not allowed",
+        415 => "Unsupported content type",
+        422 => "Body failed validation",
+        503 => "Dependency unavailable; retryable",
+        _ => "Error",
+    }
+}
+
+fn operation(op: &Operation, gen: &mut SchemaGenerator) -> Value {
+    let ok = match &op.content {
+        Content::Json(schema) => json!({
+            "200": {
+                "description": "OK",
+                "content": { "application/json": { "schema": schema(gen) } },
+            }
+        }),
+        Content::PngOrJson(schema) => json!({
+            "200": {
+                "description": "OK",
+                "content": {
+                    "image/png": { "schema": { "type": "string", "format": "binary" } },
+                    "application/json": { "schema": schema(gen) },
+                },
+            }
+        }),
+        Content::Text => json!({
+            "200": {
+                "description": "OK",
+                "content": { "text/plain": { "schema": { "type": "string" } } },
+            }
+        }),
+        Content::WebSocket => json!({
+            "101": { "description": "Switching to WebSocket; events are JSON text frames" }
+        }),
+    };
+
+    let mut responses = ok.as_object().cloned().unwrap_or_default();
+    let error = schema::<ApiError>(gen);
+    for status in op.errors.iter().chain(&[401, 403]) {
+        responses.insert(
+            status.to_string(),
+            json!({
+                "description": status_description(*status),
+                "content": { "application/json": { "schema": error } },
+            }),
+        );
+    }
+
+    let mut operation = json!({
+        "summary": op.summary,
+        "operationId": format!("{}{}", op.method, op.path.replace('/', "_")),
+        "responses": responses,
+    });
+    if let Some(query) = op.query {
+        operation["parameters"] = Value::Array(query_parameters(query(gen)));
+    }
+    if let Some(body) = op.body {
+        operation["requestBody"] = json!({
+            "required": true,
+            "content": { "application/json": { "schema": body(gen) } },
+        });
+    }
+    operation
+}
+
+/// Build the OpenAPI 3.0 document for `/v1`
+pub fn document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+
+    let mut paths = Map::new();
+    for op in OPERATIONS {
+        let item = paths.entry(op.path).or_insert_with(|| json!({}));
+        item[op.method] = operation(op, &mut gen);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "Bakable Desktop helper",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "servers": [{ "url": "/v1" }],
+        "paths": paths,
+        "security": [{ "bearer": [] }],
+        "components": {
+            "schemas": gen.take_definitions(),
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "The `token` from the helper's discovery file",
+                },
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_references_generated_schemas() {
+        let doc = document();
+        let schemas = &doc["components"]["schemas"];
+
+        assert!(schemas["ApiError"]["properties"]["retryable"].is_object());
+        assert_eq!(
+            doc["paths"]["/speech/speak"]["post"]["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/SpeakRequest"
+        );
+
+        let parameters = doc["paths"]["/capture"]["get"]["parameters"].as_array().unwrap();
+        let names: Vec<&str> = parameters.iter().filter_map(|p| p["name"].as_str()).collect();
+        assert_eq!(names, vec!["format"]);
+    }
+}
@@ -1,94 +1,1596 @@
 //! Roblox Studio plugin installer
 //!
 //! Handles installation of the Bakable.rbxm plugin file to the user's
-//! Roblox plugins directory.
+//! Roblox plugins directory. Each install writes `Bakable.manifest.json`
+//! next to the plugin recording which version went in.
+//!
+//! The plugin comes in release channels: the stable build bundled with the
+//! app, a beta build, and a local dev build. Only the selected channel's
+//! file is kept in the plugins directory so Studio never loads two copies.
+//!
+//! Installs add a `DaemonConfig` ModuleScript to the plugin with this
+//! machine's daemon URL and auth token, so it connects without setup.
+//!
+//! Stable and beta builds must carry an ed25519 signature from the key built
+//! into the app; dev builds are installed unsigned.
 
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tracing::{info, warn};
 
-/// The plugin file bundled with the app
-const PLUGIN_BYTES: &[u8] = include_bytes!("../../resources/Bakable.rbxm");
+use crate::config::{self, Config, PLUGINS_DIR_ENV};
+use crate::daemon::DAEMON_PORT;
+use crate::{capture, project, rbxm};
+
+/// The plugin file bundled with the app, built from `plugin/src` by build.rs
+const PLUGIN_BYTES: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/Bakable.rbxm"));
+
+/// Version of the bundled plugin, set by build.rs
+pub const PLUGIN_VERSION: &str = env!("BAKABLE_PLUGIN_VERSION");
+
+/// SHA-256 of the bundled plugin, set by build.rs
+pub const PLUGIN_SHA256: &str = env!("BAKABLE_PLUGIN_SHA256");
+
+/// Signature of the bundled plugin, hex; empty for unsigned builds
+const PLUGIN_SIGNATURE: &str = include_str!(concat!(env!("OUT_DIR"), "/Bakable.rbxm.sig"));
+
+/// Key stable and beta plugin builds must be signed with, hex: the public half
+/// of the release key, whose seed is `BAKABLE_PLUGIN_SIGNING_KEY` when
+/// building the app. build.rs refuses a seed that doesn't match it.
+const PLUGIN_PUBLIC_KEY: &str = include_str!("../plugin.pub");
+
+const MANIFEST_FILE: &str = "Bakable.manifest.json";
+/// The plugin replaced by the last install; Studio ignores `.bak` files
+const BACKUP_FILE: &str = "Bakable.rbxm.bak";
+const MANIFEST_BACKUP_FILE: &str = "Bakable.manifest.json.bak";
+
+/// Selected channel, in the app data directory
+const CHANNEL_FILE: &str = "plugin-channel.json";
+
+/// Names of earlier Bakable plugin builds, from before and after the rename
+/// from DetAI. Matched exactly, case included, against file stems (ignoring
+/// a ` (1)` copy suffix) and the model's root instances.
+const LEGACY_NAMES: &[&str] = &["Bakable", "DetAI"];
+
+/// ModuleScript added under the plugin's root at install, read by
+/// `State/Store.lua`
+const DAEMON_CONFIG_MODULE: &str = "DaemonConfig";
+
+/// What was installed, written next to the plugin
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    /// Missing in manifests written before channels existed
+    #[serde(default)]
+    channel: PluginChannel,
+    version: String,
+    sha256: String,
+    /// The channel's build before this machine's settings were added.
+    /// Missing in older manifests, which installed the build unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    build_sha256: Option<String>,
+    /// Unix seconds
+    installed_at: u64,
+    /// Bundled plugin that was rolled back and shouldn't be reinstalled
+    /// automatically
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    held_back: Option<String>,
+}
+
+impl Manifest {
+    fn new(channel: PluginChannel, payload: &Payload<'_>, sha256: &str) -> Self {
+        Self {
+            channel,
+            version: payload.version.clone(),
+            sha256: sha256.to_string(),
+            build_sha256: Some(payload.sha256.clone()),
+            installed_at: unix_now(),
+            held_back: None,
+        }
+    }
+
+    fn build_sha256(&self) -> &str {
+        self.build_sha256.as_deref().unwrap_or(&self.sha256)
+    }
+}
+
+/// Installed plugin compared with the selected channel's payload
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PluginStatus {
+    pub channel: PluginChannel,
+    pub installed: bool,
+    pub path: Option<PathBuf>,
+    /// From the manifest; None if it is missing or doesn't match the file
+    pub installed_version: Option<String>,
+    /// Of the file, which includes this machine's daemon settings
+    pub installed_sha256: Option<String>,
+    /// The stable plugin bundled with the app, whatever the channel
+    pub bundled_version: String,
+    pub bundled_sha256: String,
+    /// Payload of the selected channel; None for dev builds, which are
+    /// installed as they are built, or if the payload can't be read
+    pub available_version: Option<String>,
+    pub available_sha256: Option<String>,
+    /// Installed plugin differs from the channel's payload
+    pub update_available: bool,
+    /// A previous plugin is kept and `rollback_plugin` can restore it
+    pub rollback_available: bool,
+    /// The channel's payload was rolled back and won't be installed on startup
+    pub held_back: bool,
+}
+
+impl PluginStatus {
+    pub fn up_to_date(&self) -> bool {
+        self.installed && !self.update_available
+    }
+
+    /// Short label for the tray menu
+    pub fn label(&self) -> String {
+        if !self.installed {
+            return "Plugin: Not installed".to_string();
+        }
+        let version = self.installed_version.as_deref().unwrap_or("unknown");
+        let label = match self.channel {
+            PluginChannel::Stable => format!("Plugin v{}", version),
+            channel => format!("Plugin v{} [{}]", version, channel.name()),
+        };
+        match (self.held_back, self.update_available) {
+            (true, _) => format!("{} (rolled back)", label),
+            (false, true) => format!("{} (update available)", label),
+            (false, false) => label,
+        }
+    }
+}
+
+/// What an install did and what else in the plugins directory needs attention
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct InstallReport {
+    /// The plugin file was written; false if it was already current
+    pub installed: bool,
+    pub channel: PluginChannel,
+    pub path: PathBuf,
+    /// Roblox Studio is open and only loads the new plugin after a restart
+    pub studio_running: bool,
+    /// Old or duplicate Bakable plugins that Studio would load alongside
+    pub duplicates: Vec<PathBuf>,
+}
+
+impl InstallReport {
+    fn new(plugins_dir: &Path, channel: PluginChannel, installed: bool) -> Self {
+        Self {
+            installed,
+            channel,
+            path: plugins_dir.join(channel.file_name()),
+            studio_running: capture::is_studio_running(),
+            duplicates: find_duplicates(plugins_dir, channel),
+        }
+    }
+
+    /// Studio has the previous plugin loaded
+    pub fn restart_needed(&self) -> bool {
+        self.installed && self.studio_running
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// MARK: - Channels
+
+/// Release channel of the installed plugin
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginChannel {
+    /// Bundled with the app
+    #[default]
+    Stable,
+    /// Pre-release build from `plugin_channels.beta`
+    Beta,
+    /// Local build from `plugin_channels.dev` or the dev-mode project
+    Dev,
+}
+
+impl PluginChannel {
+    pub const ALL: [Self; 3] = [Self::Stable, Self::Beta, Self::Dev];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Stable => "stable",
+            Self::Beta => "beta",
+            Self::Dev => "dev",
+        }
+    }
+
+    /// Tray menu item title
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Stable => "Stable",
+            Self::Beta => "Beta",
+            Self::Dev => "Local Dev Build",
+        }
+    }
+
+    /// Tray menu item id
+    pub fn menu_id(self) -> &'static str {
+        match self {
+            Self::Stable => "channel_stable",
+            Self::Beta => "channel_beta",
+            Self::Dev => "channel_dev",
+        }
+    }
+
+    pub fn from_menu_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|channel| channel.menu_id() == id)
+    }
+
+    /// File name in the plugins directory. Each channel has its own so a
+    /// leftover from another channel is recognisable and can be removed.
+    fn file_name(self) -> &'static str {
+        match self {
+            Self::Stable => "Bakable.rbxm",
+            Self::Beta => "Bakable-beta.rbxm",
+            Self::Dev => "Bakable-dev.rbxm",
+        }
+    }
+}
+
+/// A plugin build ready to install
+struct Payload<'a> {
+    bytes: Cow<'a, [u8]>,
+    version: String,
+    /// Of the build as released, before this machine's settings are added
+    sha256: String,
+    /// ed25519 signature of the build, hex
+    signature: Option<String>,
+}
+
+impl<'a> Payload<'a> {
+    fn new(bytes: Cow<'a, [u8]>, version: String) -> Self {
+        let sha256 = sha256_hex(&bytes);
+        Self { bytes, version, sha256, signature: None }
+    }
+
+    fn with_signature(mut self, signature: Option<String>) -> Self {
+        self.signature = signature.map(|signature| signature.trim().to_string()).filter(|s| !s.is_empty());
+        self
+    }
+
+    /// Check the build was signed with `public_key`
+    fn verify(&self, public_key: &str) -> Result<(), String> {
+        use ed25519_dalek::{Signature, VerifyingKey};
+
+        let key: [u8; 32] = hex_decode(public_key)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| "This build of Bakable has no plugin signing key".to_string())?;
+        let key = VerifyingKey::from_bytes(&key)
+            .map_err(|e| format!("Invalid plugin signing key: {}", e))?;
+
+        let signature = self
+            .signature
+            .as_ref()
+            .ok_or_else(|| format!("Plugin v{} is unsigned", self.version))?;
+        let signature: [u8; 64] = hex_decode(signature)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("Plugin v{} has a malformed signature", self.version))?;
+
+        key.verify_strict(&self.bytes, &Signature::from_bytes(&signature))
+            .map_err(|_| format!("Plugin v{} signature doesn't match; it may have been tampered with", self.version))
+    }
+
+    /// The build with `settings` added as a ModuleScript under its root,
+    /// replacing any from an earlier install
+    fn configured(&self, settings: &DaemonSettings) -> Result<Payload<'static>, String> {
+        let mut roots = rbxm::read_lossless(&self.bytes)
+            .map_err(|e| format!("Plugin can't be modified: {}", e))?;
+        let root = roots.first_mut().ok_or_else(|| "Plugin has no instances".to_string())?;
+        root.children.retain(|child| child.name != DAEMON_CONFIG_MODULE);
+        root.children.push(
+            rbxm::Instance::new("ModuleScript", DAEMON_CONFIG_MODULE)
+                .with_property("Source", rbxm::Value::String(settings.module_source())),
+        );
+
+        Ok(Payload {
+            bytes: Cow::Owned(rbxm::write(&roots)?),
+            version: self.version.clone(),
+            sha256: self.sha256.clone(),
+            signature: self.signature.clone(),
+        })
+    }
+}
+
+/// This machine's daemon connection, written into the plugin at install
+#[derive(Debug, Clone)]
+pub struct DaemonSettings {
+    pub url: String,
+    pub port: u16,
+    pub token: String,
+}
+
+impl DaemonSettings {
+    pub fn current() -> Result<Self, String> {
+        Ok(Self {
+            url: format!("http://127.0.0.1:{}", DAEMON_PORT),
+            port: DAEMON_PORT,
+            token: config::daemon_token()?,
+        })
+    }
+
+    /// Luau module returning the settings
+    fn module_source(&self) -> String {
+        format!(
+            "-- Written by Bakable Desktop when the plugin was installed\n\
+             return {{\n    daemonUrl = {:?},\n    daemonPort = {},\n    daemonToken = {:?},\n}}\n",
+            self.url, self.port, self.token
+        )
+    }
+}
+
+/// Add this machine's daemon settings to `payload`. If that fails the build
+/// is installed unchanged and the daemon has to be configured by hand.
+fn for_this_machine(payload: Payload<'_>) -> Payload<'_> {
+    match DaemonSettings::current().and_then(|settings| payload.configured(&settings)) {
+        Ok(configured) => configured,
+        Err(e) => {
+            warn!("Installing the plugin without daemon settings: {}", e);
+            payload
+        }
+    }
+}
+
+/// Load the plugin build for `channel`
+fn channel_payload(channel: PluginChannel, config: &Config) -> Result<Payload<'static>, String> {
+    match channel {
+        PluginChannel::Stable => Ok(Payload::new(Cow::Borrowed(PLUGIN_BYTES), PLUGIN_VERSION.to_string())
+            .with_signature(Some(PLUGIN_SIGNATURE.to_string()))),
+        PluginChannel::Beta => {
+            let path = config
+                .plugin_channels
+                .beta_path()
+                .ok_or_else(|| "Could not determine beta plugin path".to_string())?;
+            let bytes = fs::read(&path)
+                .map_err(|e| format!("No beta plugin at {:?}: {}", path, e))?;
+            // Beta builds carry no version of their own
+            let version = format!("{}-beta+{}", PLUGIN_VERSION, &sha256_hex(&bytes)[..8]);
+            // Signed by a beta build of the app, like `$OUT_DIR/Bakable.rbxm.sig`
+            let signature = fs::read_to_string(signature_path(&path)).ok();
+            Ok(Payload::new(Cow::Owned(bytes), version).with_signature(signature))
+        }
+        PluginChannel::Dev => {
+            let path = config
+                .plugin_channels
+                .dev_path(&config.plugin_dev)
+                .ok_or_else(|| "No dev plugin configured (set plugin_channels.dev or plugin_dev)".to_string())?;
+            let bytes = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("rbxm") => fs::read(&path)
+                    .map_err(|e| format!("No dev plugin at {:?}: {}", path, e))?,
+                Some("json") => project::build(&path)?,
+                _ => {
+                    return Err(format!(
+                        "Unsupported dev plugin {:?}: expected a .rbxm build or a Rojo .project.json",
+                        path
+                    ))
+                }
+            };
+            Ok(Payload::new(Cow::Owned(bytes), format!("{}-dev", PLUGIN_VERSION)))
+        }
+    }
+}
+
+/// `channel`'s build, refused unless it is a dev build or signed with the
+/// app's key. Debug builds of the app may bundle an unsigned plugin.
+fn verified_payload(channel: PluginChannel, config: &Config) -> Result<Payload<'static>, String> {
+    let payload = channel_payload(channel, config)?;
+    match channel {
+        PluginChannel::Dev => {}
+        PluginChannel::Stable if cfg!(debug_assertions) && payload.signature.is_none() => {
+            warn!("Installing the unsigned bundled plugin (debug build)");
+        }
+        _ => payload.verify(PLUGIN_PUBLIC_KEY.trim())?,
+    }
+    Ok(payload)
+}
+
+/// Detached signature next to a plugin file: `Bakable-beta.rbxm.sig`
+fn signature_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".sig");
+    path.with_file_name(name)
+}
+
+/// Version and SHA-256 the selected channel would install, without building
+/// anything
+fn available(channel: PluginChannel, config: &Config) -> Option<(String, String)> {
+    match channel {
+        PluginChannel::Stable => Some((PLUGIN_VERSION.to_string(), PLUGIN_SHA256.to_string())),
+        PluginChannel::Beta => channel_payload(channel, config)
+            .ok()
+            .map(|payload| (payload.version, payload.sha256)),
+        PluginChannel::Dev => None,
+    }
+}
+
+/// The persisted channel choice, stable if none was made
+pub fn selected_channel() -> PluginChannel {
+    config::app_data_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(CHANNEL_FILE)).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_channel(channel: PluginChannel) -> Result<(), String> {
+    let dir = config::app_data_dir()
+        .ok_or_else(|| "Could not determine app data directory".to_string())?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    let contents = serde_json::to_vec(&channel)
+        .map_err(|e| format!("Failed to serialize plugin channel: {}", e))?;
+    write_atomic(&dir.join(CHANNEL_FILE), &contents)
+        .map_err(|e| format!("Failed to save plugin channel: {}", e))
+}
+
+/// Install `channel`'s plugin in place of the current one and remember the
+/// choice. Nothing changes if the payload can't be loaded or installed.
+pub fn switch_channel(channel: PluginChannel) -> Result<InstallReport, String> {
+    let config = Config::load();
+    let plugins_dir = get_plugins_dir(&config)?;
+    let payload = for_this_machine(verified_payload(channel, &config)?);
+    let installed = install_channel(&plugins_dir, channel, &payload, true)?;
+    save_channel(channel)?;
+    info!("Plugin channel switched to {}", channel.name());
+    Ok(InstallReport::new(&plugins_dir, channel, installed))
+}
+
+/// Remove plugin files left by other channels, with the backup of whatever
+/// they replaced. Returns whether any were found.
+fn remove_other_channels(plugins_dir: &Path, keep: PluginChannel) -> bool {
+    let mut removed = false;
+    for channel in PluginChannel::ALL.into_iter().filter(|channel| *channel != keep) {
+        let path = plugins_dir.join(channel.file_name());
+        if path.exists() {
+            match fs::remove_file(&path) {
+                Ok(()) => {
+                    info!("Removed {} channel plugin {:?}", channel.name(), path);
+                    removed = true;
+                }
+                Err(e) => warn!("Failed to remove {:?}: {}", path, e),
+            }
+        }
+    }
+    if removed {
+        let _ = fs::remove_file(plugins_dir.join(BACKUP_FILE));
+        let _ = fs::remove_file(plugins_dir.join(MANIFEST_BACKUP_FILE));
+    }
+    removed
+}
+
+// MARK: - Plugins Directory
+
+/// Where a candidate plugins directory came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginsDirSource {
+    /// `BAKABLE_PLUGINS_DIR`
+    Env,
+    /// `plugins_dir` in config.json
+    Config,
+    /// Studio's own location on macOS or Windows
+    Native,
+    /// A Vinegar Wine prefix (Flatpak or native)
+    Vinegar,
+    /// A Grapejuice Wine prefix
+    Grapejuice,
+    /// `WINEPREFIX` or `~/.wine`
+    Wine,
+}
+
+/// A directory Studio might load plugins from
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PluginsDirCandidate {
+    pub path: PathBuf,
+    pub source: PluginsDirSource,
+    /// The Plugins folder itself exists
+    pub exists: bool,
+    /// The Roblox folder above it exists, so Studio has run here
+    pub roblox_found: bool,
+}
+
+/// Every candidate plugins directory and the one installs use
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PluginsDirResolution {
+    pub chosen: Option<PathBuf>,
+    /// Why `chosen` was picked, or why nothing was
+    pub reason: String,
+    pub candidates: Vec<PluginsDirCandidate>,
+}
+
+impl PluginsDirCandidate {
+    fn new(path: PathBuf, source: PluginsDirSource) -> Self {
+        Self {
+            exists: path.is_dir(),
+            roblox_found: path.parent().is_some_and(Path::is_dir),
+            path,
+            source,
+        }
+    }
+}
+
+fn source_name(source: PluginsDirSource) -> &'static str {
+    match source {
+        PluginsDirSource::Env => "BAKABLE_PLUGINS_DIR",
+        PluginsDirSource::Config => "config.json",
+        PluginsDirSource::Native => "Studio's default location",
+        PluginsDirSource::Vinegar => "the Vinegar prefix",
+        PluginsDirSource::Grapejuice => "the Grapejuice prefix",
+        PluginsDirSource::Wine => "the Wine prefix",
+    }
+}
+
+/// Resolve the Roblox plugins directory: an explicit override, then Studio's
+/// native location, then (on Linux) the first Wine prefix Studio has run in
+pub fn resolve_plugins_dir(config: &Config) -> PluginsDirResolution {
+    let overrides = [
+        std::env::var_os(PLUGINS_DIR_ENV).map(|dir| (PathBuf::from(dir), PluginsDirSource::Env)),
+        config.plugins_dir.clone().map(|dir| (dir, PluginsDirSource::Config)),
+    ];
+
+    let mut candidates: Vec<PluginsDirCandidate> = overrides
+        .into_iter()
+        .flatten()
+        .map(|(path, source)| PluginsDirCandidate::new(path, source))
+        .collect();
+    candidates.extend(platform_candidates());
+    choose(candidates)
+}
+
+fn choose(candidates: Vec<PluginsDirCandidate>) -> PluginsDirResolution {
+    use PluginsDirSource::*;
+
+    let explicit = candidates.iter().find(|c| matches!(c.source, Env | Config | Native)).map(|c| {
+        let reason = match c.source {
+            Native => "Studio's default location on this platform".to_string(),
+            _ => format!("Set by {}", source_name(c.source)),
+        };
+        (c, reason)
+    });
+    let chosen = explicit.or_else(|| {
+        // Prefer a prefix that already has plugins, then one where Studio has run
+        candidates
+            .iter()
+            .find(|c| c.exists)
+            .map(|c| (c, format!("Plugins folder exists in {}", source_name(c.source))))
+            .or_else(|| {
+                candidates.iter().find(|c| c.roblox_found).map(|c| {
+                    (c, format!("Roblox is installed in {}; Plugins will be created", source_name(c.source)))
+                })
+            })
+    });
+
+    match chosen {
+        Some((candidate, reason)) => PluginsDirResolution {
+            chosen: Some(candidate.path.clone()),
+            reason,
+            candidates,
+        },
+        None => PluginsDirResolution {
+            chosen: None,
+            reason: format!(
+                "No Wine or Vinegar prefix with Roblox Studio found; set {} to choose one",
+                PLUGINS_DIR_ENV
+            ),
+            candidates,
+        },
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn platform_candidates() -> Vec<PluginsDirCandidate> {
+    // ~/Documents/Roblox/Plugins
+    dirs::home_dir()
+        .map(|home| PluginsDirCandidate::new(home.join("Documents/Roblox/Plugins"), PluginsDirSource::Native))
+        .into_iter()
+        .collect()
+}
+
+#[cfg(target_os = "windows")]
+fn platform_candidates() -> Vec<PluginsDirCandidate> {
+    // %LOCALAPPDATA%\Roblox\Plugins
+    dirs::data_local_dir()
+        .map(|dir| PluginsDirCandidate::new(dir.join("Roblox/Plugins"), PluginsDirSource::Native))
+        .into_iter()
+        .collect()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn platform_candidates() -> Vec<PluginsDirCandidate> {
+    let home = dirs::home_dir();
+    let wine_prefix = std::env::var_os("WINEPREFIX").map(PathBuf::from);
+    prefix_candidates(home.as_deref(), dirs::data_dir().as_deref(), wine_prefix)
+}
+
+/// Plugins folders inside the Wine prefixes Studio launchers use, in order
+/// of preference
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn prefix_candidates(
+    home: Option<&Path>,
+    data_dir: Option<&Path>,
+    wine_prefix: Option<PathBuf>,
+) -> Vec<PluginsDirCandidate> {
+    use PluginsDirSource::*;
+
+    let mut prefixes: Vec<(PathBuf, PluginsDirSource)> = Vec::new();
+    if let Some(home) = home {
+        prefixes.push((home.join(".var/app/org.vinegarhq.Vinegar/data/vinegar/prefixes/studio"), Vinegar));
+    }
+    if let Some(data) = data_dir {
+        prefixes.push((data.join("vinegar/prefixes/studio"), Vinegar));
+        // Vinegar before prefixes were split per binary
+        prefixes.push((data.join("vinegar/pfx"), Vinegar));
+        if let Ok(entries) = fs::read_dir(data.join("grapejuice/prefixes")) {
+            let mut grapejuice: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+            grapejuice.sort();
+            prefixes.extend(grapejuice.into_iter().map(|prefix| (prefix, Grapejuice)));
+        }
+    }
+    prefixes.extend(wine_prefix.map(|prefix| (prefix, Wine)));
+    if let Some(home) = home {
+        prefixes.push((home.join(".wine"), Wine));
+    }
+
+    let mut candidates: Vec<PluginsDirCandidate> = Vec::new();
+    for (prefix, source) in prefixes {
+        for user in prefix_users(&prefix) {
+            let path = user.join("AppData/Local/Roblox/Plugins");
+            if !candidates.iter().any(|c| c.path == path) {
+                candidates.push(PluginsDirCandidate::new(path, source));
+            }
+        }
+    }
+    candidates
+}
+
+/// Windows user profiles inside a Wine prefix
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn prefix_users(prefix: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(prefix.join("drive_c/users")) else {
+        return Vec::new();
+    };
+    let mut users: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_name() != "Public" && entry.path().is_dir())
+        .map(|entry| entry.path())
+        .collect();
+    users.sort();
+    users
+}
 
 /// Get the Roblox plugins directory path
-fn get_plugins_dir() -> Option<PathBuf> {
-    let home = dirs::home_dir()?;
+pub(crate) fn get_plugins_dir(config: &Config) -> Result<PathBuf, String> {
+    resolve_plugins_dir(config)
+        .chosen
+        .ok_or_else(|| "Could not determine plugins directory".to_string())
+}
 
-    // macOS: ~/Documents/Roblox/Plugins
-    #[cfg(target_os = "macos")]
-    let plugins_dir = home.join("Documents/Roblox/Plugins");
+/// Install the selected channel's plugin to the Roblox plugins directory,
+/// reporting whether anything was written, Err on failure
+pub fn install_plugin() -> Result<InstallReport, String> {
+    let config = Config::load();
+    let plugins_dir = get_plugins_dir(&config)?;
+    install_selected(&plugins_dir, &config, false)
+}
 
-    // Windows: %LOCALAPPDATA%\Roblox\Plugins
-    #[cfg(target_os = "windows")]
-    let plugins_dir = dirs::data_local_dir()?.join("Roblox/Plugins");
+/// Install the selected channel's plugin even if it was rolled back
+pub fn reinstall_plugin() -> Result<InstallReport, String> {
+    let config = Config::load();
+    let plugins_dir = get_plugins_dir(&config)?;
+    install_selected(&plugins_dir, &config, true)
+}
 
-    Some(plugins_dir)
+/// Install a plugin built from source in dev mode, replacing whatever is
+/// installed. Dev mode selects the dev channel.
+pub fn install_dev_build(bytes: &[u8]) -> Result<bool, String> {
+    let plugins_dir = get_plugins_dir(&Config::load())?;
+    let payload = for_this_machine(Payload::new(Cow::Borrowed(bytes), format!("{}-dev", PLUGIN_VERSION)));
+    let installed = install_channel(&plugins_dir, PluginChannel::Dev, &payload, true)?;
+    if selected_channel() != PluginChannel::Dev {
+        save_channel(PluginChannel::Dev)?;
+    }
+    Ok(installed)
 }
 
-/// Install the plugin to the Roblox plugins directory
-/// Returns Ok(true) if installed, Ok(false) if already exists, Err on failure
-pub fn install_plugin() -> Result<bool, String> {
-    let plugins_dir = get_plugins_dir()
-        .ok_or_else(|| "Could not determine plugins directory".to_string())?;
+/// The selected channel, or stable if it's dev and the dev build's source
+/// is gone, e.g. after a dev-mode session. Falling back is remembered.
+fn installable_channel(config: &Config) -> PluginChannel {
+    let channel = selected_channel();
+    let has_source = config
+        .plugin_channels
+        .dev_path(&config.plugin_dev)
+        .is_some_and(|path| path.exists());
+    if channel != PluginChannel::Dev || has_source {
+        return channel;
+    }
+
+    warn!("No dev plugin source is configured; falling back to the stable plugin");
+    if let Err(e) = save_channel(PluginChannel::Stable) {
+        warn!("{}", e);
+    }
+    PluginChannel::Stable
+}
+
+fn install_selected(plugins_dir: &Path, config: &Config, force: bool) -> Result<InstallReport, String> {
+    let channel = installable_channel(config);
+    let payload = for_this_machine(verified_payload(channel, config)?);
+    let installed = install_channel(plugins_dir, channel, &payload, force)?;
+    Ok(InstallReport::new(plugins_dir, channel, installed))
+}
+
+/// Install `payload` as `channel`'s plugin, then remove other channels'
+/// files so Studio only loads one copy
+fn install_channel(
+    plugins_dir: &Path,
+    channel: PluginChannel,
+    payload: &Payload<'_>,
+    force: bool,
+) -> Result<bool, String> {
+    let installed = install_bytes(plugins_dir, channel, payload, force)?;
+    remove_other_channels(plugins_dir, channel);
+    Ok(installed)
+}
+
+fn install_bytes(
+    plugins_dir: &Path,
+    channel: PluginChannel,
+    payload: &Payload<'_>,
+    force: bool,
+) -> Result<bool, String> {
+    let sha256 = sha256_hex(&payload.bytes);
+    let version = &payload.version;
 
     // Create directory if it doesn't exist
     if !plugins_dir.exists() {
         info!("Creating plugins directory: {:?}", plugins_dir);
-        fs::create_dir_all(&plugins_dir)
+        fs::create_dir_all(plugins_dir)
             .map_err(|e| format!("Failed to create plugins directory: {}", e))?;
     }
 
-    let plugin_path = plugins_dir.join("Bakable.rbxm");
+    let plugin_path = plugins_dir.join(channel.file_name());
+    let manifest = read_manifest(plugins_dir);
 
     // Check if plugin already exists with same content
     if plugin_path.exists() {
         let existing = fs::read(&plugin_path)
             .map_err(|e| format!("Failed to read existing plugin: {}", e))?;
+        let existing_sha256 = sha256_hex(&existing);
 
-        if existing == PLUGIN_BYTES {
+        if existing_sha256 == sha256 {
             info!("Plugin already installed and up to date");
+            // Installs from before the manifest existed get one now
+            if manifest.as_ref().is_none_or(|m| m.sha256 != sha256) {
+                write_manifest(plugins_dir, &Manifest::new(channel, payload, &sha256))?;
+            }
+            return Ok(false);
+        }
+
+        if !force && manifest.as_ref().and_then(|m| m.held_back.as_deref()) == Some(payload.sha256.as_str()) {
+            info!("Plugin v{} was rolled back, keeping the previous plugin", version);
             return Ok(false);
         }
 
         info!("Plugin exists but outdated, updating...");
-    }
 
-    // Write the plugin file
-    let mut file = fs::File::create(&plugin_path)
-        .map_err(|e| format!("Failed to create plugin file: {}", e))?;
+        // Keep the outgoing plugin so it can be restored
+        write_atomic(&plugins_dir.join(BACKUP_FILE), &existing)
+            .map_err(|e| format!("Failed to back up plugin: {}", e))?;
+        match manifest.filter(|m| m.sha256 == existing_sha256) {
+            Some(manifest) => {
+                let contents = serde_json::to_vec_pretty(&manifest)
+                    .map_err(|e| format!("Failed to serialize plugin manifest: {}", e))?;
+                write_atomic(&plugins_dir.join(MANIFEST_BACKUP_FILE), &contents)
+                    .map_err(|e| format!("Failed to back up plugin manifest: {}", e))?;
+            }
+            None => {
+                let _ = fs::remove_file(plugins_dir.join(MANIFEST_BACKUP_FILE));
+            }
+        }
+    }
 
-    file.write_all(PLUGIN_BYTES)
+    // Studio never sees a partially written plugin
+    write_atomic(&plugin_path, &payload.bytes)
         .map_err(|e| format!("Failed to write plugin file: {}", e))?;
 
-    info!("Plugin installed to {:?}", plugin_path);
+    write_manifest(plugins_dir, &Manifest::new(channel, payload, &sha256))?;
+
+    info!("Plugin v{} installed to {:?}", version, plugin_path);
     Ok(true)
 }
 
-/// Check if plugin is installed
-pub fn is_plugin_installed() -> bool {
-    get_plugins_dir()
-        .map(|dir| dir.join("Bakable.rbxm").exists())
-        .unwrap_or(false)
+/// Restore the plugin that the last install replaced. The replaced version
+/// is then held back from automatic installs until reinstalled from the tray.
+pub fn rollback_plugin() -> Result<(), String> {
+    let plugins_dir = get_plugins_dir(&Config::load())?;
+    rollback_in(&plugins_dir, selected_channel())
 }
 
-/// Get the plugin file path
+fn rollback_in(plugins_dir: &Path, channel: PluginChannel) -> Result<(), String> {
+    let backup_path = plugins_dir.join(BACKUP_FILE);
+    let previous = fs::read(&backup_path)
+        .map_err(|e| format!("No previous plugin to restore: {}", e))?;
+
+    // Hold back the build that was installed, not this machine's copy of it
+    let plugin_path = plugins_dir.join(channel.file_name());
+    let replaced = fs::read(&plugin_path).ok().map(|bytes| sha256_hex(&bytes));
+    let replaced_build = read_manifest(plugins_dir)
+        .filter(|manifest| Some(&manifest.sha256) == replaced.as_ref())
+        .map(|manifest| manifest.build_sha256().to_string())
+        .or(replaced);
+    write_atomic(&plugin_path, &previous)
+        .map_err(|e| format!("Failed to restore plugin: {}", e))?;
+
+    let sha256 = sha256_hex(&previous);
+    let previous_manifest = fs::read_to_string(plugins_dir.join(MANIFEST_BACKUP_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Manifest>(&contents).ok());
+    let manifest = Manifest {
+        channel,
+        version: previous_manifest
+            .as_ref()
+            .map(|manifest| manifest.version.clone())
+            .unwrap_or_else(|| "unknown".to_string()),
+        build_sha256: previous_manifest
+            .as_ref()
+            .map(|manifest| manifest.build_sha256().to_string()),
+        sha256,
+        installed_at: unix_now(),
+        held_back: replaced_build,
+    };
+    write_manifest(plugins_dir, &manifest)?;
+
+    let _ = fs::remove_file(&backup_path);
+    let _ = fs::remove_file(plugins_dir.join(MANIFEST_BACKUP_FILE));
+
+    info!("Plugin rolled back to v{}", manifest.version);
+    Ok(())
+}
+
+/// Write `bytes` to a temp file next to `path`, fsync it, then rename it
+/// over `path` so readers see either the old or the new contents
+pub(crate) fn write_atomic(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("plugin");
+    // Hidden and without the .rbxm extension, so Studio won't load it
+    let temp_path = dir.join(format!(".{}.tmp", name));
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)?;
+
+        // Persist the rename itself
+        #[cfg(unix)]
+        fs::File::open(dir)?.sync_all()?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn read_manifest(plugins_dir: &Path) -> Option<Manifest> {
+    let contents = fs::read_to_string(plugins_dir.join(MANIFEST_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_manifest(plugins_dir: &Path, manifest: &Manifest) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize plugin manifest: {}", e))?;
+    write_atomic(&plugins_dir.join(MANIFEST_FILE), contents.as_bytes())
+        .map_err(|e| format!("Failed to write plugin manifest: {}", e))
+}
+
+/// Compare the installed plugin with the selected channel's payload
+pub fn plugin_status() -> PluginStatus {
+    let config = Config::load();
+    let plugins_dir = get_plugins_dir(&config).ok();
+    let channel = selected_channel();
+    status_in(plugins_dir.as_deref(), channel, available(channel, &config))
+}
+
+fn status_in(
+    plugins_dir: Option<&Path>,
+    channel: PluginChannel,
+    available: Option<(String, String)>,
+) -> PluginStatus {
+    let (available_version, available_sha256) = available.unzip();
+    let path = plugins_dir.map(|dir| dir.join(channel.file_name()));
+    let installed_sha256 = path
+        .as_ref()
+        .and_then(|path| fs::read(path).ok())
+        .map(|bytes| sha256_hex(&bytes));
+
+    // A manifest only counts if it describes the file that is actually there
+    let manifest = plugins_dir
+        .and_then(read_manifest)
+        .filter(|manifest| Some(&manifest.sha256) == installed_sha256.as_ref());
+    let held_back = manifest
+        .as_ref()
+        .is_some_and(|manifest| manifest.held_back.is_some() && manifest.held_back == available_sha256);
+    // Compare builds, since the file also carries this machine's settings
+    let installed_build = manifest
+        .as_ref()
+        .map(|manifest| manifest.build_sha256().to_string())
+        .or_else(|| installed_sha256.clone());
+    let update_available = match (&installed_build, &available_sha256) {
+        (Some(installed), Some(available)) => installed != available,
+        _ => false,
+    };
+
+    PluginStatus {
+        channel,
+        installed: installed_sha256.is_some(),
+        update_available,
+        rollback_available: plugins_dir.is_some_and(|dir| dir.join(BACKUP_FILE).is_file()),
+        held_back,
+        path,
+        installed_version: manifest.map(|manifest| manifest.version),
+        installed_sha256,
+        bundled_version: PLUGIN_VERSION.to_string(),
+        bundled_sha256: PLUGIN_SHA256.to_string(),
+        available_version,
+        available_sha256,
+    }
+}
+
+// MARK: - Duplicates
+
+/// Plugin files other than the selected channel's that look like another
+/// copy of Bakable: named after it (`DetAI.rbxm`, `Bakable (1).rbxm`), or a
+/// renamed `.rbxm` whose root is the plugin
+fn find_duplicates(plugins_dir: &Path, channel: PluginChannel) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(plugins_dir) else { return Vec::new() };
+    let mut duplicates: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_duplicate(path, channel))
+        .collect();
+    duplicates.sort();
+    duplicates
+}
+
+fn is_duplicate(path: &Path, channel: PluginChannel) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else { return false };
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase);
+    if name == channel.file_name() || !matches!(extension.as_deref(), Some("rbxm" | "rbxmx")) {
+        return false;
+    }
+
+    if is_channel_file(name) {
+        return true;
+    }
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default();
+    let stem = match stem.rsplit_once(" (") {
+        Some((base, copy)) if copy.strip_suffix(')').is_some_and(|n| n.parse::<u32>().is_ok()) => base,
+        _ => stem,
+    };
+    if LEGACY_NAMES.contains(&stem) {
+        return true;
+    }
+
+    // Only binary models can be inspected
+    extension.as_deref() == Some("rbxm")
+        && fs::read(path)
+            .ok()
+            .and_then(|bytes| rbxm::read(&bytes).ok())
+            .is_some_and(|roots| roots.iter().any(|root| LEGACY_NAMES.contains(&root.name.as_str())))
+}
+
+/// Delete the duplicates an install reported. Returns the files removed.
+pub fn remove_duplicates() -> Result<Vec<PathBuf>, String> {
+    let plugins_dir = get_plugins_dir(&Config::load())?;
+
+    let mut removed = Vec::new();
+    for path in find_duplicates(&plugins_dir, selected_channel()) {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove {:?}: {}", path, e))?;
+        info!("Removed duplicate plugin {:?}", path);
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+// MARK: - Contents
+
+/// One instance in a plugin, addressed by its path from the root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+pub struct PluginEntry {
+    /// e.g. `Bakable/Sync/DaemonClient`
+    pub path: String,
+    pub class_name: String,
+    /// SHA-256 of `Source`, for scripts
+    pub source_sha256: Option<String>,
+}
+
+/// Installed plugin contents compared script by script with the selected
+/// channel's payload
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PluginDiff {
+    pub installed_path: Option<PathBuf>,
+    /// None if not installed or unreadable
+    pub installed: Option<Vec<PluginEntry>>,
+    /// Why the installed plugin couldn't be read
+    pub installed_error: Option<String>,
+    /// Empty if the channel's payload can't be loaded
+    pub bundled: Vec<PluginEntry>,
+    /// Installed plugin has the same instances and script sources
+    pub identical: bool,
+    /// Scripts in both whose source or class differs
+    pub changed: Vec<String>,
+    /// Instances only in the installed plugin, e.g. hand-added scripts
+    pub only_installed: Vec<String>,
+    /// Instances only in the bundled plugin
+    pub only_bundled: Vec<String>,
+}
+
+/// Flatten a model into entries, depth-first
+fn plugin_entries(bytes: &[u8]) -> Result<Vec<PluginEntry>, String> {
+    fn visit(instance: &rbxm::Instance, parent: &str, entries: &mut Vec<PluginEntry>) {
+        let path = match parent {
+            "" => instance.name.clone(),
+            _ => format!("{}/{}", parent, instance.name),
+        };
+        entries.push(PluginEntry {
+            path: path.clone(),
+            class_name: instance.class_name.clone(),
+            source_sha256: instance.string("Source").map(|source| sha256_hex(source.as_bytes())),
+        });
+        for child in &instance.children {
+            visit(child, &path, entries);
+        }
+    }
+
+    let mut entries = Vec::new();
+    for mut root in rbxm::read(bytes)? {
+        // Added at install, not part of the build
+        root.children.retain(|child| child.name != DAEMON_CONFIG_MODULE);
+        visit(&root, "", &mut entries);
+    }
+    Ok(entries)
+}
+
+/// Paths that differ between `a` and `b`: changed, only in `a`, only in `b`
+fn compare_entries(a: &[PluginEntry], b: &[PluginEntry]) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut changed = Vec::new();
+    let mut only_a = Vec::new();
+    for entry in a {
+        match b.iter().find(|other| other.path == entry.path) {
+            Some(other) if other != entry => changed.push(entry.path.clone()),
+            Some(_) => {}
+            None => only_a.push(entry.path.clone()),
+        }
+    }
+    let only_b = b
+        .iter()
+        .filter(|other| !a.iter().any(|entry| entry.path == other.path))
+        .map(|other| other.path.clone())
+        .collect();
+    (changed, only_a, only_b)
+}
+
+/// Scripts and instances that differ between two builds of the plugin, all
+/// paths in one list
+pub fn changed_paths(before: &[u8], after: &[u8]) -> Result<Vec<String>, String> {
+    let (mut changed, added, removed) = compare_entries(&plugin_entries(after)?, &plugin_entries(before)?);
+    changed.extend(added);
+    changed.extend(removed);
+    Ok(changed)
+}
+
+/// List the installed and available plugin trees and which scripts differ
+pub fn plugin_diff() -> PluginDiff {
+    let config = Config::load();
+    let payload = channel_payload(selected_channel(), &config);
+    let bytes = payload.as_ref().map(|payload| &payload.bytes[..]).unwrap_or_default();
+    diff_installed(plugin_path(&config), bytes)
+}
+
+fn diff_installed(installed_path: Option<PathBuf>, bundled: &[u8]) -> PluginDiff {
+    let bundled = plugin_entries(bundled).unwrap_or_default();
+
+    let installed = match installed_path.as_ref().map(fs::read) {
+        Some(Ok(bytes)) => plugin_entries(&bytes).map(Some),
+        Some(Err(e)) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Some(Err(e)) => Err(format!("Failed to read installed plugin: {}", e)),
+        None => Ok(None),
+    };
+    let (installed, installed_error) = match installed {
+        Ok(installed) => (installed, None),
+        Err(e) => (None, Some(e)),
+    };
+
+    let (changed, only_installed, only_bundled) = installed
+        .as_ref()
+        .map(|installed| compare_entries(installed, &bundled))
+        .unwrap_or_default();
+
+    PluginDiff {
+        identical: installed.is_some() && changed.is_empty() && only_installed.is_empty() && only_bundled.is_empty(),
+        installed_path,
+        installed,
+        installed_error,
+        bundled,
+        changed,
+        only_installed,
+        only_bundled,
+    }
+}
+
+/// Whether `name` is one of the files Bakable installs for a channel
+pub(crate) fn is_channel_file(name: &str) -> bool {
+    PluginChannel::ALL.iter().any(|channel| channel.file_name() == name)
+}
+
+/// Get the selected channel's plugin file path
 pub fn get_plugin_path() -> Option<PathBuf> {
-    get_plugins_dir().map(|dir| dir.join("Bakable.rbxm"))
+    plugin_path(&Config::load())
+}
+
+fn plugin_path(config: &Config) -> Option<PathBuf> {
+    get_plugins_dir(config).ok().map(|dir| dir.join(selected_channel().file_name()))
 }
 
-/// Uninstall the plugin
-pub fn uninstall_plugin() -> Result<(), String> {
-    let plugin_path = get_plugin_path()
-        .ok_or_else(|| "Could not determine plugin path".to_string())?;
+/// Remove the plugin from every channel, with its manifest and backup.
+/// Returns whether there was anything to remove.
+pub fn uninstall_plugin() -> Result<bool, String> {
+    let plugins_dir = get_plugins_dir(&Config::load())?;
+    uninstall_in(&plugins_dir, selected_channel())
+}
 
+fn uninstall_in(plugins_dir: &Path, channel: PluginChannel) -> Result<bool, String> {
+    let plugin_path = plugins_dir.join(channel.file_name());
+    let mut removed = false;
     if plugin_path.exists() {
         fs::remove_file(&plugin_path)
             .map_err(|e| format!("Failed to remove plugin: {}", e))?;
-        info!("Plugin uninstalled");
+        removed = true;
+    }
+    removed |= remove_other_channels(plugins_dir, channel);
+    for file in [MANIFEST_FILE, BACKUP_FILE, MANIFEST_BACKUP_FILE] {
+        let _ = fs::remove_file(plugins_dir.join(file));
+    }
+
+    if removed {
+        info!("Plugin uninstalled from {:?}", plugins_dir);
     } else {
         warn!("Plugin not found, nothing to uninstall");
     }
+    Ok(removed)
+}
 
-    Ok(())
+/// Program that shows a folder in the platform's file manager
+#[cfg(target_os = "macos")]
+const FOLDER_OPENER: &str = "open";
+#[cfg(target_os = "windows")]
+const FOLDER_OPENER: &str = "explorer";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const FOLDER_OPENER: &str = "xdg-open";
+
+/// Show the resolved plugins directory in the file manager, creating it if
+/// Studio hasn't yet. Returns the directory opened.
+pub fn open_plugins_dir() -> Result<PathBuf, String> {
+    let plugins_dir = get_plugins_dir(&Config::load())?;
+    fs::create_dir_all(&plugins_dir)
+        .map_err(|e| format!("Failed to create {:?}: {}", plugins_dir, e))?;
+    std::process::Command::new(FOLDER_OPENER)
+        .arg(&plugins_dir)
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", FOLDER_OPENER, e))?;
+    Ok(plugins_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> DaemonSettings {
+        DaemonSettings {
+            url: "http://127.0.0.1:4849".to_string(),
+            port: 4849,
+            token: "test-token".to_string(),
+        }
+    }
+
+    fn install_into(dir: &Path, force: bool) -> Result<bool, String> {
+        let payload = channel_payload(PluginChannel::Stable, &Config::default()).unwrap();
+        install_channel(dir, PluginChannel::Stable, &payload.configured(&settings()).unwrap(), force)
+    }
+
+    fn stable_status(dir: &Path) -> PluginStatus {
+        status_in(Some(dir), PluginChannel::Stable, available(PluginChannel::Stable, &Config::default()))
+    }
+
+    /// Candidates for a home where Studio has run under Wine and Grapejuice
+    /// already has a Plugins folder, with those two folders
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    fn wine_candidates(root: &Path) -> (Vec<PluginsDirCandidate>, PathBuf, PathBuf) {
+        let home = root.join("home");
+        let data = home.join(".local/share");
+
+        let wine_roblox = home.join(".wine/drive_c/users/me/AppData/Local/Roblox");
+        fs::create_dir_all(&wine_roblox).unwrap();
+        fs::create_dir_all(home.join(".wine/drive_c/users/Public")).unwrap();
+        let grapejuice = data.join("grapejuice/prefixes/player/drive_c/users/me/AppData/Local/Roblox/Plugins");
+        fs::create_dir_all(&grapejuice).unwrap();
+
+        (prefix_candidates(Some(&home), Some(&data), None), grapejuice, wine_roblox)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[test]
+    fn finds_plugins_dirs_in_wine_prefixes() {
+        let temp = tempfile::tempdir().unwrap();
+        let (candidates, _, _) = wine_candidates(temp.path());
+
+        let sources: Vec<PluginsDirSource> = candidates.iter().map(|c| c.source).collect();
+        assert_eq!(sources, vec![PluginsDirSource::Grapejuice, PluginsDirSource::Wine]);
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[test]
+    fn prefers_an_existing_plugins_folder() {
+        let temp = tempfile::tempdir().unwrap();
+        let (candidates, grapejuice, _) = wine_candidates(temp.path());
+
+        let resolution = choose(candidates);
+        assert_eq!(resolution.chosen, Some(grapejuice));
+        assert_eq!(resolution.reason, "Plugins folder exists in the Grapejuice prefix");
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[test]
+    fn falls_back_to_the_prefix_roblox_is_installed_in() {
+        let temp = tempfile::tempdir().unwrap();
+        let (candidates, _, wine_roblox) = wine_candidates(temp.path());
+
+        let resolution = choose(candidates[1..].to_vec());
+        assert_eq!(resolution.chosen, Some(wine_roblox.join("Plugins")));
+        assert!(resolution.reason.starts_with("Roblox is installed in the Wine prefix"));
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[test]
+    fn plugins_dir_override_wins_even_if_missing() {
+        let temp = tempfile::tempdir().unwrap();
+        let (candidates, _, _) = wine_candidates(temp.path());
+
+        let custom = temp.path().join("custom");
+        let mut with_override = vec![PluginsDirCandidate::new(custom.clone(), PluginsDirSource::Env)];
+        with_override.extend(candidates);
+        let resolution = choose(with_override);
+        assert_eq!(resolution.chosen, Some(custom));
+        assert_eq!(resolution.reason, "Set by BAKABLE_PLUGINS_DIR");
+        assert_eq!(resolution.candidates.len(), 3);
+    }
+
+    #[test]
+    fn no_candidates_choose_nothing() {
+        assert_eq!(choose(Vec::new()).chosen, None);
+    }
+
+    #[test]
+    fn install_records_manifest() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        let status = stable_status(dir);
+        assert!(!status.installed);
+        assert_eq!(status.label(), "Plugin: Not installed");
+
+        assert_eq!(install_into(dir, false), Ok(true));
+        let status = stable_status(dir);
+        assert!(status.up_to_date());
+        assert!(!status.rollback_available);
+        assert_eq!(status.installed_version.as_deref(), Some(PLUGIN_VERSION));
+        assert!(!dir.join(".Bakable.rbxm.tmp").exists());
+    }
+
+    #[test]
+    fn install_skips_an_up_to_date_plugin() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        assert_eq!(install_into(dir, false), Ok(true));
+        assert_eq!(install_into(dir, false), Ok(false));
+    }
+
+    #[test]
+    fn installed_plugin_carries_daemon_settings() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        install_into(dir, false).unwrap();
+
+        // The installed copy carries the daemon settings on top of the build
+        let path = dir.join(PluginChannel::Stable.file_name());
+        assert_ne!(stable_status(dir).installed_sha256.as_deref(), Some(PLUGIN_SHA256));
+        let roots = rbxm::read(&fs::read(&path).unwrap()).unwrap();
+        let module = roots[0].children.iter().find(|c| c.name == DAEMON_CONFIG_MODULE).unwrap();
+        assert_eq!(module.class_name, "ModuleScript");
+        let source = module.string("Source").unwrap();
+        assert!(source.contains(r#"daemonUrl = "http://127.0.0.1:4849""#));
+        assert!(source.contains(r#"daemonToken = "test-token""#));
+        assert!(diff_installed(Some(path), PLUGIN_BYTES).identical);
+    }
+
+    #[test]
+    fn older_plugin_on_disk_needs_an_update() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        install_into(dir, false).unwrap();
+
+        // An older plugin on disk invalidates the manifest
+        fs::write(dir.join(PluginChannel::Stable.file_name()), b"old plugin").unwrap();
+        let status = stable_status(dir);
+        assert!(status.update_available);
+        assert_eq!(status.installed_version, None);
+        assert_eq!(status.label(), "Plugin vunknown (update available)");
+
+        assert_eq!(install_into(dir, false), Ok(true));
+        let status = stable_status(dir);
+        assert!(status.up_to_date());
+        assert!(status.rollback_available);
+        assert!(dir.join(BACKUP_FILE).exists());
+    }
+
+    #[test]
+    fn rollback_restores_the_previous_plugin_and_holds_back() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join(PluginChannel::Stable.file_name()), b"old plugin").unwrap();
+        install_into(dir, false).unwrap();
+
+        rollback_in(dir, PluginChannel::Stable).unwrap();
+        assert_eq!(fs::read(dir.join(PluginChannel::Stable.file_name())).unwrap(), b"old plugin");
+        let status = stable_status(dir);
+        assert!(status.held_back);
+        assert!(!status.rollback_available);
+        assert_eq!(status.label(), "Plugin vunknown (rolled back)");
+        assert!(rollback_in(dir, PluginChannel::Stable).is_err());
+
+        // Only a forced install replaces a held back plugin
+        assert_eq!(install_into(dir, false), Ok(false));
+        assert_eq!(install_into(dir, true), Ok(true));
+        assert!(!stable_status(dir).held_back);
+    }
+
+    #[test]
+    fn switching_channels_removes_other_plugins() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("Bakable.rbxm"), b"old plugin").unwrap();
+        install_into(dir, false).unwrap();
+
+        let beta = Payload::new(Cow::Borrowed(b"beta plugin"), "1.0.0-beta".to_string());
+        assert_eq!(install_channel(dir, PluginChannel::Beta, &beta, false), Ok(true));
+        assert!(dir.join("Bakable-beta.rbxm").exists());
+        assert!(!dir.join("Bakable.rbxm").exists());
+        assert!(!dir.join(BACKUP_FILE).exists());
+
+        let status = status_in(
+            Some(dir),
+            PluginChannel::Beta,
+            Some(("1.0.0-beta".to_string(), sha256_hex(b"beta plugin"))),
+        );
+        assert!(status.up_to_date());
+        assert_eq!(status.label(), "Plugin v1.0.0-beta [beta]");
+
+        // Switching back removes the beta plugin
+        assert!(!stable_status(dir).installed);
+        assert_eq!(install_into(dir, false), Ok(true));
+        assert!(!dir.join("Bakable-beta.rbxm").exists());
+        assert!(stable_status(dir).up_to_date());
+    }
+
+    #[test]
+    fn uninstall_removes_every_channel_and_the_manifest() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        install_into(dir, false).unwrap();
+        fs::write(dir.join("Bakable-dev.rbxm"), b"dev plugin").unwrap();
+
+        assert_eq!(uninstall_in(dir, PluginChannel::Stable), Ok(true));
+        assert!(!dir.join("Bakable.rbxm").exists());
+        assert!(!dir.join("Bakable-dev.rbxm").exists());
+        assert!(!dir.join(MANIFEST_FILE).exists());
+        assert!(!stable_status(dir).installed);
+        assert_eq!(uninstall_in(dir, PluginChannel::Stable), Ok(false));
+    }
+
+    #[test]
+    fn channels_have_menu_ids_and_json_names() {
+        assert_eq!(PluginChannel::from_menu_id("channel_dev"), Some(PluginChannel::Dev));
+        assert_eq!(serde_json::to_string(&PluginChannel::Beta).unwrap(), "\"beta\"");
+    }
+
+    #[test]
+    fn dev_builds_come_from_rbxm_or_project_files() {
+        let mut config = Config::default();
+        config.plugin_channels.dev = Some(PathBuf::from("/plugins/Bakable.rbxmx"));
+        let err = channel_payload(PluginChannel::Dev, &config).err().unwrap();
+        assert!(err.contains("Unsupported dev plugin"), "{}", err);
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Public key and signature of the bundled plugin under a test key
+    fn test_signature() -> (String, String) {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let key = SigningKey::from_bytes(&[7; 32]);
+        (hex(key.verifying_key().as_bytes()), hex(&key.sign(PLUGIN_BYTES).to_bytes()))
+    }
+
+    #[test]
+    fn verifies_signed_payloads() {
+        let (public_key, signature) = test_signature();
+        let signed = Payload::new(Cow::Borrowed(PLUGIN_BYTES), "1.0.0".to_string())
+            .with_signature(Some(format!("{}\n", signature)));
+        assert_eq!(signed.verify(&public_key), Ok(()));
+    }
+
+    #[test]
+    fn rejects_payloads_without_a_signing_key() {
+        let (_, signature) = test_signature();
+        let signed = Payload::new(Cow::Borrowed(PLUGIN_BYTES), "1.0.0".to_string()).with_signature(Some(signature));
+        assert_eq!(signed.verify(""), Err("This build of Bakable has no plugin signing key".to_string()));
+    }
+
+    #[test]
+    fn rejects_other_keys_and_tampered_payloads() {
+        use ed25519_dalek::SigningKey;
+
+        let (public_key, signature) = test_signature();
+        let signed = Payload::new(Cow::Borrowed(PLUGIN_BYTES), "1.0.0".to_string())
+            .with_signature(Some(signature.clone()));
+        let other_key = hex(SigningKey::from_bytes(&[8; 32]).verifying_key().as_bytes());
+        assert!(signed.verify(&other_key).unwrap_err().contains("doesn't match"));
+
+        let tampered = Payload::new(Cow::Borrowed(b"tampered"), "1.0.0".to_string()).with_signature(Some(signature));
+        assert!(tampered.verify(&public_key).unwrap_err().contains("doesn't match"));
+    }
+
+    #[test]
+    fn rejects_unsigned_payloads() {
+        let (public_key, _) = test_signature();
+        let unsigned = Payload::new(Cow::Borrowed(PLUGIN_BYTES), "1.0.0".to_string()).with_signature(Some(String::new()));
+        assert_eq!(unsigned.verify(&public_key), Err("Plugin v1.0.0 is unsigned".to_string()));
+    }
+
+    #[test]
+    fn signatures_sit_next_to_the_plugin() {
+        assert_eq!(
+            signature_path(Path::new("/plugins/Bakable-beta.rbxm")),
+            PathBuf::from("/plugins/Bakable-beta.rbxm.sig")
+        );
+    }
+
+    fn write_plugins(dir: &Path, files: &[(&str, &[u8])]) {
+        for (name, contents) in files {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn finds_legacy_and_copied_plugins() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let other = rbxm::write(&[rbxm::Instance::new("Folder", "OtherPlugin")]).unwrap();
+        write_plugins(
+            dir,
+            &[
+                ("Bakable.rbxm", PLUGIN_BYTES),
+                ("Bakable.rbxm.bak", PLUGIN_BYTES),
+                ("DetAI.rbxm", b"legacy"),
+                ("Bakable (1).rbxmx", b"<roblox/>"),
+                ("Renamed.rbxm", PLUGIN_BYTES),
+                ("OtherPlugin.rbxm", &other),
+                ("BakableTools.rbxm", &other),
+                ("notes.txt", b"Bakable"),
+            ],
+        );
+
+        assert_eq!(
+            find_duplicates(dir, PluginChannel::Stable),
+            vec![dir.join("Bakable (1).rbxmx"), dir.join("DetAI.rbxm"), dir.join("Renamed.rbxm")]
+        );
+    }
+
+    #[test]
+    fn legacy_names_match_with_their_case() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let lowercase = rbxm::write(&[rbxm::Instance::new("Folder", "bakable")]).unwrap();
+        let other = rbxm::write(&[rbxm::Instance::new("Folder", "OtherPlugin")]).unwrap();
+        write_plugins(dir, &[("detai (2).rbxm", &other), ("Lowercase.rbxm", &lowercase)]);
+
+        assert!(find_duplicates(dir, PluginChannel::Stable).is_empty());
+    }
+
+    #[test]
+    fn other_channels_plugins_are_duplicates() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        write_plugins(dir, &[("Bakable.rbxm", PLUGIN_BYTES)]);
+
+        assert!(find_duplicates(dir, PluginChannel::Stable).is_empty());
+        assert_eq!(find_duplicates(dir, PluginChannel::Beta), vec![dir.join("Bakable.rbxm")]);
+    }
+
+    #[test]
+    fn diff_without_an_installed_plugin() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(PluginChannel::Stable.file_name());
+        assert!(diff_installed(Some(path), PLUGIN_BYTES).installed.is_none());
+    }
+
+    #[test]
+    fn diff_of_the_bundled_plugin_is_identical() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(PluginChannel::Stable.file_name());
+        fs::write(&path, PLUGIN_BYTES).unwrap();
+
+        let diff = diff_installed(Some(path), PLUGIN_BYTES);
+        assert!(diff.identical);
+        assert!(diff.bundled.iter().any(|e| e.path == "Bakable/Sync/DaemonClient" && e.source_sha256.is_some()));
+    }
+
+    #[test]
+    fn diff_reports_edited_scripts() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(PluginChannel::Stable.file_name());
+
+        // Edit one script and add another
+        let mut roots = rbxm::read(PLUGIN_BYTES).unwrap();
+        let sync = roots[0].children.iter_mut().find(|c| c.name == "Sync").unwrap();
+        sync.children[0].properties.insert("Source".into(), rbxm::Value::String("-- edited".into()));
+        sync.children.push(rbxm::Instance::new("ModuleScript", "Extra"));
+        fs::write(&path, rbxm::write(&roots).unwrap()).unwrap();
+
+        let diff = diff_installed(Some(path), PLUGIN_BYTES);
+        assert!(!diff.identical);
+        assert_eq!(diff.changed, vec!["Bakable/Sync/DaemonClient"]);
+        assert_eq!(diff.only_installed, vec!["Bakable/Sync/Extra"]);
+        assert!(diff.only_bundled.is_empty());
+    }
+
+    #[test]
+    fn diff_reports_unreadable_plugins() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join(PluginChannel::Stable.file_name());
+        fs::write(&path, b"garbage").unwrap();
+        assert!(diff_installed(Some(path), PLUGIN_BYTES).installed_error.is_some());
+    }
 }
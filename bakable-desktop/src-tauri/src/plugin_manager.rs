@@ -0,0 +1,289 @@
+//! Manage every plugin in the Roblox plugins directory, not just Bakable
+//!
+//! Disabling a plugin moves it to a sibling `Plugins.disabled` folder, where
+//! Studio doesn't look, and enabling moves it back. Nothing is deleted.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tracing::info;
+
+use crate::config::Config;
+use crate::{plugin, rbxm};
+
+/// File types Studio loads as plugins
+const PLUGIN_EXTENSIONS: &[&str] = &["rbxm", "rbxmx", "lua"];
+
+/// A plugin file, enabled or disabled
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ManagedPlugin {
+    /// File name, e.g. `Bakable.rbxm`
+    pub name: String,
+    pub path: PathBuf,
+    /// Size in bytes
+    pub size: u64,
+    /// Last modified, Unix time in seconds
+    pub modified: Option<u64>,
+    /// In the plugins directory rather than `Plugins.disabled`
+    pub enabled: bool,
+    /// Installed by Bakable; managed through install and uninstall instead
+    pub bakable: bool,
+}
+
+/// `Plugins.disabled` next to `plugins_dir`
+fn disabled_dir(plugins_dir: &Path) -> PathBuf {
+    let name = plugins_dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Plugins".to_string());
+    plugins_dir.with_file_name(format!("{}.disabled", name))
+}
+
+fn is_plugin_file(path: &Path) -> bool {
+    let extension = path.extension().and_then(|ext| ext.to_str()).map(str::to_ascii_lowercase);
+    path.is_file() && extension.is_some_and(|ext| PLUGIN_EXTENSIONS.contains(&ext.as_str()))
+}
+
+/// Reject anything that isn't a plain file name, so `name` can't escape the
+/// plugins directory
+fn check_name(name: &str) -> Result<(), String> {
+    let plain = !name.is_empty()
+        && Path::new(name).file_name().and_then(|n| n.to_str()) == Some(name)
+        && !name.starts_with('.');
+    if plain {
+        Ok(())
+    } else {
+        Err(format!("Invalid plugin name: {:?}", name))
+    }
+}
+
+fn managed_plugin(path: PathBuf, enabled: bool) -> Option<ManagedPlugin> {
+    let metadata = fs::metadata(&path).ok()?;
+    let name = path.file_name()?.to_str()?.to_string();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    Some(ManagedPlugin {
+        bakable: plugin::is_channel_file(&name),
+        name,
+        path,
+        size: metadata.len(),
+        modified,
+        enabled,
+    })
+}
+
+// MARK: - Listing
+
+/// Every plugin file, enabled and disabled
+pub fn list(config: &Config) -> Result<Vec<ManagedPlugin>, String> {
+    list_in(&plugin::get_plugins_dir(config)?)
+}
+
+fn list_in(plugins_dir: &Path) -> Result<Vec<ManagedPlugin>, String> {
+    let mut plugins = Vec::new();
+    for (dir, enabled) in [(plugins_dir.to_path_buf(), true), (disabled_dir(plugins_dir), false)] {
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to read {:?}: {}", dir, e)),
+        };
+        plugins.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| is_plugin_file(path))
+                .filter_map(|path| managed_plugin(path, enabled)),
+        );
+    }
+    plugins.sort_by_key(|plugin| plugin.name.to_lowercase());
+    Ok(plugins)
+}
+
+// MARK: - Enable / Disable
+
+/// Move a plugin to `Plugins.disabled` so Studio stops loading it
+pub fn disable(config: &Config, name: &str) -> Result<ManagedPlugin, String> {
+    set_enabled_in(&plugin::get_plugins_dir(config)?, name, false)
+}
+
+/// Move a disabled plugin back into the plugins directory
+pub fn enable(config: &Config, name: &str) -> Result<ManagedPlugin, String> {
+    set_enabled_in(&plugin::get_plugins_dir(config)?, name, true)
+}
+
+fn set_enabled_in(plugins_dir: &Path, name: &str, enabled: bool) -> Result<ManagedPlugin, String> {
+    check_name(name)?;
+    if plugin::is_channel_file(name) {
+        return Err(format!("{} is managed by Bakable; uninstall it instead", name));
+    }
+
+    let disabled_dir = disabled_dir(plugins_dir);
+    let (from_dir, to_dir) = if enabled {
+        (disabled_dir.as_path(), plugins_dir)
+    } else {
+        (plugins_dir, disabled_dir.as_path())
+    };
+    let from = from_dir.join(name);
+    let to = to_dir.join(name);
+
+    if !is_plugin_file(&from) {
+        if is_plugin_file(&to) {
+            return managed_plugin(to, enabled).ok_or_else(|| format!("Failed to read {}", name));
+        }
+        return Err(format!("No plugin named {}", name));
+    }
+    if to.exists() {
+        return Err(format!("{:?} already exists", to));
+    }
+
+    fs::create_dir_all(to_dir).map_err(|e| format!("Failed to create {:?}: {}", to_dir, e))?;
+    fs::rename(&from, &to).map_err(|e| format!("Failed to move {} to {:?}: {}", name, to_dir, e))?;
+    info!("{} plugin {}", if enabled { "Enabled" } else { "Disabled" }, name);
+
+    managed_plugin(to, enabled).ok_or_else(|| format!("Failed to read {}", name))
+}
+
+// MARK: - Import
+
+/// Copy a plugin file into the plugins directory
+pub fn import(config: &Config, source: &Path) -> Result<ManagedPlugin, String> {
+    import_into(&plugin::get_plugins_dir(config)?, source)
+}
+
+fn import_into(plugins_dir: &Path, source: &Path) -> Result<ManagedPlugin, String> {
+    if !is_plugin_file(source) {
+        return Err(format!(
+            "{:?} is not a plugin file (expected .rbxm, .rbxmx or .lua)",
+            source
+        ));
+    }
+    let name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid plugin file name: {:?}", source))?;
+    check_name(name)?;
+    if plugin::is_channel_file(name) {
+        return Err(format!("{} is managed by Bakable; rename it to import", name));
+    }
+
+    let target = plugins_dir.join(name);
+    if target.exists() || disabled_dir(plugins_dir).join(name).exists() {
+        return Err(format!("A plugin named {} is already installed", name));
+    }
+
+    let bytes = fs::read(source).map_err(|e| format!("Failed to read {:?}: {}", source, e))?;
+    if name.to_ascii_lowercase().ends_with(".rbxm") {
+        rbxm::read(&bytes).map_err(|e| format!("{} is not a valid model: {}", name, e))?;
+    }
+
+    fs::create_dir_all(plugins_dir).map_err(|e| format!("Failed to create {:?}: {}", plugins_dir, e))?;
+    plugin::write_atomic(&target, &bytes).map_err(|e| format!("Failed to write {:?}: {}", target, e))?;
+    info!("Imported plugin {} from {:?}", name, source);
+
+    managed_plugin(target, true).ok_or_else(|| format!("Failed to read {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A plugins directory with `Tool.lua`, the Bakable plugin and its
+    /// manifest
+    fn plugins_dir(root: &Path) -> PathBuf {
+        let plugins = root.join("Plugins");
+        fs::create_dir_all(&plugins).unwrap();
+        fs::write(plugins.join("Tool.lua"), "print('hi')").unwrap();
+        fs::write(plugins.join("Bakable.rbxm"), b"bakable").unwrap();
+        fs::write(plugins.join("Bakable.manifest.json"), b"{}").unwrap();
+        plugins
+    }
+
+    #[test]
+    fn lists_plugin_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+
+        let listed = list_in(&plugins).unwrap();
+        let names: Vec<&str> = listed.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["Bakable.rbxm", "Tool.lua"]);
+        assert!(listed[0].bakable);
+        assert_eq!(listed[1].size, 11);
+        assert!(listed[1].modified.is_some());
+    }
+
+    #[test]
+    fn disabling_moves_a_plugin_to_plugins_disabled() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+
+        let disabled = set_enabled_in(&plugins, "Tool.lua", false).unwrap();
+        assert!(!disabled.enabled);
+        assert_eq!(disabled.path, temp.path().join("Plugins.disabled/Tool.lua"));
+        assert!(!plugins.join("Tool.lua").exists());
+        assert!(!list_in(&plugins).unwrap()[1].enabled);
+    }
+
+    #[test]
+    fn enabling_moves_a_plugin_back() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+        set_enabled_in(&plugins, "Tool.lua", false).unwrap();
+
+        assert!(set_enabled_in(&plugins, "Tool.lua", true).unwrap().enabled);
+        assert!(plugins.join("Tool.lua").exists());
+    }
+
+    #[test]
+    fn bakable_plugin_cannot_be_disabled() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+        assert!(set_enabled_in(&plugins, "Bakable.rbxm", false).is_err());
+    }
+
+    #[test]
+    fn names_outside_the_plugins_dir_are_rejected() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+        assert!(set_enabled_in(&plugins, "../Tool.lua", false).is_err());
+    }
+
+    #[test]
+    fn missing_plugins_cannot_be_moved() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+        assert!(set_enabled_in(&plugins, "Missing.lua", false).is_err());
+    }
+
+    #[test]
+    fn imports_a_plugin_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+        let source = temp.path().join("Other.lua");
+        fs::write(&source, "return {}").unwrap();
+
+        let imported = import_into(&plugins, &source).unwrap();
+        assert_eq!(imported.path, plugins.join("Other.lua"));
+        assert!(import_into(&plugins, &source).is_err());
+    }
+
+    #[test]
+    fn import_rejects_invalid_models() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+        let bogus = temp.path().join("Bogus.rbxm");
+        fs::write(&bogus, b"not a model").unwrap();
+        assert!(import_into(&plugins, &bogus).is_err());
+    }
+
+    #[test]
+    fn import_rejects_files_that_arent_plugins() {
+        let temp = tempfile::tempdir().unwrap();
+        let plugins = plugins_dir(temp.path());
+        assert!(import_into(&plugins, &plugins.join("Bakable.manifest.json")).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! Rojo project loading
+//!
+//! Builds the Studio plugin from `plugin/default.project.json` and its
+//! sources the way `rojo build` does for what the plugin uses: `$path`,
+//! `$className`, simple `$properties`, `globIgnorePaths`, folders, `init`
+//! scripts, `.txt` files and `.rbxm` models. Shared by build.rs, which
+//! bundles the plugin, and dev-mode hot reload.
+
+use serde_json::Value as Json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::rbxm::{self, Instance, Value};
+
+/// Where a project lives and which of its files to leave out
+struct Project {
+    dir: PathBuf,
+    /// `globIgnorePaths`, relative to `dir`
+    ignore: Vec<String>,
+}
+
+impl Project {
+    fn is_ignored(&self, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(&self.dir) else {
+            return false;
+        };
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.ignore.iter().any(|pattern| glob_match(pattern, &relative))
+    }
+}
+
+/// Build a project file into rbxm bytes
+pub fn build(project_path: &Path) -> Result<Vec<u8>, String> {
+    let contents = fs::read_to_string(project_path)
+        .map_err(|e| format!("Failed to read {}: {}", project_path.display(), e))?;
+    let json: Json = serde_json::from_str(&contents)
+        .map_err(|e| format!("Invalid project file {}: {}", project_path.display(), e))?;
+
+    let name = json["name"]
+        .as_str()
+        .ok_or_else(|| format!("{}: missing \"name\"", project_path.display()))?;
+    let tree = json
+        .get("tree")
+        .ok_or_else(|| format!("{}: missing \"tree\"", project_path.display()))?;
+    let ignore = match json.get("globIgnorePaths") {
+        Some(patterns) => patterns
+            .as_array()
+            .and_then(|patterns| patterns.iter().map(|p| p.as_str().map(String::from)).collect())
+            .ok_or_else(|| format!("{}: globIgnorePaths must be a list of strings", project_path.display()))?,
+        None => Vec::new(),
+    };
+
+    let project = Project {
+        dir: project_path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        ignore,
+    };
+    let root = project_node(name, tree, &project)?;
+    rbxm::write(&[root])
+}
+
+/// The project file and every `$path` in its tree, which are all that can
+/// change its build. Just the project file if it can't be parsed.
+pub fn source_paths(project_path: &Path) -> Vec<PathBuf> {
+    fn collect(node: &Json, project_dir: &Path, paths: &mut Vec<PathBuf>) {
+        let Some(node) = node.as_object() else { return };
+        if let Some(path) = node.get("$path").and_then(Json::as_str) {
+            paths.push(project_dir.join(path));
+        }
+        for (key, child) in node {
+            if !key.starts_with('$') {
+                collect(child, project_dir, paths);
+            }
+        }
+    }
+
+    let mut paths = vec![project_path.to_path_buf()];
+    let project = fs::read_to_string(project_path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Json>(&contents).ok());
+    if let Some(tree) = project.as_ref().and_then(|project| project.get("tree")) {
+        collect(tree, project_path.parent().unwrap_or(Path::new(".")), &mut paths);
+    }
+    paths
+}
+
+/// A node of the project tree: `$path` and/or `$className`, `$properties`,
+/// and children under any key not starting with `$`
+fn project_node(name: &str, node: &Json, project: &Project) -> Result<Instance, String> {
+    let node = node
+        .as_object()
+        .ok_or_else(|| format!("Project node {:?} is not an object", name))?;
+
+    let class_name = match node.get("$className") {
+        Some(class_name) => Some(
+            class_name
+                .as_str()
+                .ok_or_else(|| format!("Project node {:?}: $className must be a string", name))?,
+        ),
+        None => None,
+    };
+
+    let mut instance = match (node.get("$path"), class_name) {
+        (Some(path), _) => {
+            let path = path
+                .as_str()
+                .ok_or_else(|| format!("Project node {:?}: $path must be a string", name))?;
+            let path = project.dir.join(path);
+            let mut instance = path_instance(&path, project)?
+                .ok_or_else(|| format!("Project node {:?}: $path {} is ignored", name, path.display()))?;
+            instance.name = name.to_string();
+            if let Some(class_name) = class_name {
+                instance.class_name = class_name.to_string();
+            }
+            instance
+        }
+        (None, Some(class_name)) => Instance::new(class_name, name),
+        (None, None) => return Err(format!("Project node {:?} has neither $className nor $path", name)),
+    };
+
+    if let Some(properties) = node.get("$properties") {
+        let properties = properties
+            .as_object()
+            .ok_or_else(|| format!("Project node {:?}: $properties must be an object", name))?;
+        for (property, value) in properties {
+            let value = match value {
+                Json::String(value) => Value::String(value.clone()),
+                Json::Bool(value) => Value::Bool(*value),
+                other => {
+                    return Err(format!(
+                        "Project node {:?}: {} must be a string or bool, got {}",
+                        name,
+                        property,
+                        json_type(other)
+                    ))
+                }
+            };
+            instance.properties.insert(property.clone(), value);
+        }
+    }
+
+    for (key, child) in node {
+        if !key.starts_with('$') {
+            instance.children.push(project_node(key, child, project)?);
+        }
+    }
+    Ok(instance)
+}
+
+fn json_type(value: &Json) -> &'static str {
+    match value {
+        Json::Null => "null",
+        Json::Bool(_) => "bool",
+        Json::Number(_) => "number",
+        Json::String(_) => "string",
+        Json::Array(_) => "array",
+        Json::Object(_) => "object",
+    }
+}
+
+/// Match `path` against a `globIgnorePaths` pattern: `*` stays within one
+/// path segment, `**/` spans any number of directories
+fn glob_match(pattern: &str, path: &str) -> bool {
+    if let Some(rest) = pattern.strip_prefix("**/") {
+        return glob_match(rest, path)
+            || path.split_once('/').is_some_and(|(_, tail)| glob_match(pattern, tail));
+    }
+    match pattern.chars().next() {
+        None => path.is_empty(),
+        Some('*') => {
+            let rest = &pattern[1..];
+            path.char_indices()
+                .take_while(|&(_, c)| c != '/')
+                .map(|(i, _)| i)
+                .chain(std::iter::once(path.find('/').unwrap_or(path.len())))
+                .any(|i| glob_match(rest, &path[i..]))
+        }
+        Some(c) => path.starts_with(c) && glob_match(&pattern[c.len_utf8()..], &path[c.len_utf8()..]),
+    }
+}
+
+/// Script class and instance name for a Lua file, following Rojo:
+/// `x.server.lua` is a Script, `x.client.lua` a LocalScript, `x.lua` a
+/// ModuleScript (`.luau` likewise)
+fn script_kind(file_name: &str) -> Option<(&'static str, &str)> {
+    let stem = file_name
+        .strip_suffix(".luau")
+        .or_else(|| file_name.strip_suffix(".lua"))?;
+    Some(match stem {
+        _ if stem.ends_with(".server") => ("Script", &stem[..stem.len() - ".server".len()]),
+        _ if stem.ends_with(".client") => ("LocalScript", &stem[..stem.len() - ".client".len()]),
+        _ => ("ModuleScript", stem),
+    })
+}
+
+fn script(class_name: &str, name: &str, path: &Path) -> Result<Instance, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(Instance::new(class_name, name).with_property("Source", Value::String(source)))
+}
+
+/// A file that isn't a script: `x.txt` is a StringValue, `x.rbxm` the single
+/// model inside it, renamed `x`
+fn file_instance(file_name: &str, path: &Path) -> Result<Instance, String> {
+    if let Some(name) = file_name.strip_suffix(".txt") {
+        let value = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        return Ok(Instance::new("StringValue", name).with_property("Value", Value::String(value)));
+    }
+    if let Some(name) = file_name.strip_suffix(".rbxm") {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut roots = rbxm::read_lossless(&bytes).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if roots.len() != 1 {
+            return Err(format!("{} must contain exactly one instance", path.display()));
+        }
+        let mut instance = roots.remove(0);
+        instance.name = name.to_string();
+        return Ok(instance);
+    }
+    Err(format!(
+        "Unsupported file {} (expected .lua, .luau, .txt or .rbxm; add it to globIgnorePaths to leave it out)",
+        path.display()
+    ))
+}
+
+/// A directory becomes a Folder, or the script from its `init` file with the
+/// other entries as children. None if the project ignores `path`.
+fn path_instance(path: &Path, project: &Project) -> Result<Option<Instance>, String> {
+    if project.is_ignored(path) {
+        return Ok(None);
+    }
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("Invalid path {}", path.display()))?;
+
+    if !path.is_dir() {
+        return match script_kind(file_name) {
+            Some((class_name, name)) => script(class_name, name, path).map(Some),
+            None => file_instance(file_name, path).map(Some),
+        };
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    entries.sort();
+
+    let mut instance: Option<Instance> = None;
+    let mut children = Vec::new();
+    for entry in entries {
+        let entry_name = entry.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        if entry_name.starts_with('.') || project.is_ignored(&entry) {
+            continue;
+        }
+        match script_kind(entry_name) {
+            Some((class_name, "init")) if !entry.is_dir() => {
+                if instance.is_some() {
+                    return Err(format!("{} has more than one init script", path.display()));
+                }
+                instance = Some(script(class_name, file_name, &entry)?);
+            }
+            _ => children.extend(path_instance(&entry, project)?),
+        }
+    }
+
+    let mut instance = instance.unwrap_or_else(|| Instance::new("Folder", file_name));
+    instance.children = children;
+    Ok(Some(instance))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_folders_and_init_scripts() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("src/Tools")).unwrap();
+        fs::create_dir_all(dir.join("src/UI")).unwrap();
+        fs::write(dir.join("src/init.server.lua"), "print('main')").unwrap();
+        fs::write(dir.join("src/Tools/init.lua"), "return {}").unwrap();
+        fs::write(dir.join("src/Tools/Query.luau"), "return 1").unwrap();
+        fs::write(dir.join("src/UI/Panel.client.lua"), "").unwrap();
+        fs::write(dir.join("src/UI/notes.txt"), "hello").unwrap();
+        fs::write(dir.join("src/UI/Panel.test.lua"), "").unwrap();
+        fs::write(
+            dir.join("default.project.json"),
+            r#"{
+                "name": "Demo",
+                "globIgnorePaths": ["**/*.test.lua"],
+                "tree": {"$path": "src", "Extra": {"$className": "Folder"}}
+            }"#,
+        )
+        .unwrap();
+
+        let roots = rbxm::read(&build(&dir.join("default.project.json")).unwrap()).unwrap();
+        let root = &roots[0];
+        assert_eq!((root.class_name.as_str(), root.name.as_str()), ("Script", "Demo"));
+        assert_eq!(root.string("Source"), Some("print('main')"));
+
+        let children: Vec<(&str, &str)> =
+            root.children.iter().map(|c| (c.class_name.as_str(), c.name.as_str())).collect();
+        assert_eq!(children, vec![("ModuleScript", "Tools"), ("Folder", "UI"), ("Folder", "Extra")]);
+        assert_eq!(root.children[0].children[0].name, "Query");
+        let ui: Vec<(&str, &str)> =
+            root.children[1].children.iter().map(|c| (c.class_name.as_str(), c.name.as_str())).collect();
+        assert_eq!(ui, vec![("LocalScript", "Panel"), ("StringValue", "notes")]);
+        assert_eq!(root.children[1].children[1].string("Value"), Some("hello"));
+
+        // Files Rojo would need a middleware for are an error, not dropped
+        fs::write(dir.join("src/UI/logo.png"), "").unwrap();
+        let err = build(&dir.join("default.project.json")).unwrap_err();
+        assert!(err.contains("logo.png"), "{}", err);
+        fs::remove_file(dir.join("src/UI/logo.png")).unwrap();
+
+        // Two init scripts in one folder is a malformed project
+        fs::write(dir.join("src/Tools/init.server.lua"), "").unwrap();
+        assert!(build(&dir.join("default.project.json")).is_err());
+
+        fs::write(dir.join("default.project.json"), r#"{"name": "Demo", "tree": {}}"#).unwrap();
+        assert!(build(&dir.join("default.project.json")).is_err());
+
+        let number = r#"{"name": "Demo", "tree": {"$className": "Folder", "$properties": {"Size": 3}}}"#;
+        fs::write(dir.join("default.project.json"), number).unwrap();
+        let err = build(&dir.join("default.project.json")).unwrap_err();
+        assert!(err.contains("Size must be a string or bool, got number"), "{}", err);
+    }
+
+    #[test]
+    fn matches_ignore_globs() {
+        assert!(glob_match("**/*.test.lua", "src/DevTools/Query.test.lua"));
+        assert!(glob_match("**/*.test.lua", "Query.test.lua"));
+        assert!(glob_match("src/*.txt", "src/notes.txt"));
+        assert!(!glob_match("src/*.txt", "src/sub/notes.txt"));
+        assert!(!glob_match("**/*.test.lua", "src/Query.lua"));
+    }
+}
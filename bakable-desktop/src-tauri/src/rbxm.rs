@@ -0,0 +1,492 @@
+//! Roblox binary model (`.rbxm`) format
+//!
+//! Covers what the Studio plugin is made of: Folders and scripts with string
+//! and bool properties. Reading skips property types it doesn't know, so any
+//! model can be inspected. Also compiled into build.rs, so dependencies here
+//! are build dependencies too.
+//!
+//! A file is a header followed by chunks: `INST` per class, `PROP` per class
+//! and property, `PRNT` for the hierarchy and `END`. Chunks are written
+//! uncompressed, which Studio accepts; LZ4 chunks are read as well.
+
+use std::collections::BTreeMap;
+
+const MAGIC: &[u8] = b"<roblox!\x89\xff\x0d\x0a\x1a\x0a";
+const END_MARKER: &[u8] = b"</roblox>";
+
+/// Property type ids; `ProtectedString` (script `Source`) is stored as a string
+const TYPE_STRING: u8 = 0x01;
+const TYPE_BOOL: u8 = 0x02;
+
+/// A property value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    String(String),
+    /// A string property that isn't UTF-8, such as `AttributesSerialize`;
+    /// kept as is so it's written back unchanged
+    Bytes(Vec<u8>),
+    Bool(bool),
+}
+
+impl Value {
+    fn type_id(&self) -> u8 {
+        match self {
+            Self::String(_) | Self::Bytes(_) => TYPE_STRING,
+            Self::Bool(_) => TYPE_BOOL,
+        }
+    }
+}
+
+/// An instance and its descendants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instance {
+    pub class_name: String,
+    pub name: String,
+    /// Properties other than `Name`
+    pub properties: BTreeMap<String, Value>,
+    pub children: Vec<Instance>,
+}
+
+impl Instance {
+    pub fn new(class_name: &str, name: &str) -> Self {
+        Self {
+            class_name: class_name.to_string(),
+            name: name.to_string(),
+            properties: BTreeMap::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Builder-style property setter
+    pub fn with_property(mut self, name: &str, value: Value) -> Self {
+        self.properties.insert(name.to_string(), value);
+        self
+    }
+
+    /// String property, e.g. a script's `Source`
+    pub fn string(&self, name: &str) -> Option<&str> {
+        match self.properties.get(name) {
+            Some(Value::String(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+// MARK: - Reading
+
+/// Byte cursor that errors instead of panicking on truncated input
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.offset >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("Unexpected end of data at byte {}", self.offset))?;
+        let slice = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default()))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+
+    /// A string for display, such as a class or property name
+    fn string(&mut self) -> Result<String, String> {
+        Ok(String::from_utf8_lossy(self.bytes()?).into_owned())
+    }
+
+    /// A string property value, as `Bytes` if it isn't UTF-8
+    fn string_value(&mut self) -> Result<Value, String> {
+        let bytes = self.bytes()?;
+        Ok(match std::str::from_utf8(bytes) {
+            Ok(value) => Value::String(value.to_string()),
+            Err(_) => Value::Bytes(bytes.to_vec()),
+        })
+    }
+
+    fn referents(&mut self, count: usize) -> Result<Vec<i32>, String> {
+        let bytes = self.take(count.checked_mul(4).ok_or("Referent count overflows")?)?;
+        let mut last = 0i32;
+        Ok((0..count)
+            .map(|i| {
+                let raw = u32::from_be_bytes([
+                    bytes[i],
+                    bytes[count + i],
+                    bytes[count * 2 + i],
+                    bytes[count * 3 + i],
+                ]);
+                let delta = ((raw >> 1) as i32) ^ -((raw & 1) as i32);
+                last = last.wrapping_add(delta);
+                last
+            })
+            .collect())
+    }
+}
+
+/// Read the chunk at `reader`, decompressing it if needed
+fn read_chunk<'a>(reader: &mut Reader<'a>) -> Result<([u8; 4], Vec<u8>), String> {
+    let name: [u8; 4] = reader.take(4)?.try_into().unwrap_or_default();
+    let compressed_len = reader.u32()? as usize;
+    let len = reader.u32()? as usize;
+    reader.take(4)?;
+
+    let data = match compressed_len {
+        0 => reader.take(len)?.to_vec(),
+        _ => {
+            let compressed = reader.take(compressed_len)?;
+            if compressed.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+                return Err("Zstandard-compressed chunks are not supported".to_string());
+            }
+            lz4_flex::block::decompress(compressed, len)
+                .map_err(|e| format!("Corrupt {} chunk: {}", String::from_utf8_lossy(&name), e))?
+        }
+    };
+    Ok((name, data))
+}
+
+/// Parse a binary model into its root instances. Properties of types other
+/// than string and bool are skipped.
+pub fn read(bytes: &[u8]) -> Result<Vec<Instance>, String> {
+    parse(bytes, false)
+}
+
+/// Parse a binary model, failing if it has properties that would be lost,
+/// so it can be modified and written back
+pub fn read_lossless(bytes: &[u8]) -> Result<Vec<Instance>, String> {
+    parse(bytes, true)
+}
+
+fn parse(bytes: &[u8], lossless: bool) -> Result<Vec<Instance>, String> {
+    let mut reader = Reader::new(bytes);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err("Not a binary Roblox model".to_string());
+    }
+    reader.take(2 + 4 + 4 + 8)?;
+
+    // Class id -> (class name, referents), and referent -> instance
+    let mut classes: BTreeMap<u32, (String, Vec<i32>)> = BTreeMap::new();
+    let mut instances: BTreeMap<i32, Instance> = BTreeMap::new();
+    let mut hierarchy: Vec<(i32, i32)> = Vec::new();
+
+    while !reader.is_empty() {
+        let (name, data) = read_chunk(&mut reader)?;
+        let mut chunk = Reader::new(&data);
+        match &name {
+            b"INST" => {
+                let class_id = chunk.u32()?;
+                let class_name = chunk.string()?;
+                chunk.u8()?;
+                let count = chunk.u32()? as usize;
+                let referents = chunk.referents(count)?;
+                for referent in &referents {
+                    instances.insert(*referent, Instance::new(&class_name, ""));
+                }
+                classes.insert(class_id, (class_name, referents));
+            }
+            b"PROP" => {
+                let class_id = chunk.u32()?;
+                let property = chunk.string()?;
+                let type_id = chunk.u8()?;
+                let Some((_, referents)) = classes.get(&class_id) else {
+                    return Err(format!("Property {} for unknown class {}", property, class_id));
+                };
+                for referent in referents {
+                    let value = match type_id {
+                        TYPE_STRING => chunk.string_value()?,
+                        TYPE_BOOL => Value::Bool(chunk.u8()? != 0),
+                        _ if lossless => {
+                            return Err(format!("Unsupported type {} for property {}", type_id, property));
+                        }
+                        // Other types aren't needed to inspect scripts
+                        _ => break,
+                    };
+                    let Some(instance) = instances.get_mut(referent) else { continue };
+                    match (property.as_str(), value) {
+                        ("Name", Value::String(name)) => instance.name = name,
+                        ("Name", Value::Bytes(name)) => instance.name = String::from_utf8_lossy(&name).into_owned(),
+                        (_, value) => {
+                            instance.properties.insert(property.clone(), value);
+                        }
+                    }
+                }
+            }
+            b"PRNT" => {
+                chunk.u8()?;
+                let count = chunk.u32()? as usize;
+                let children = chunk.referents(count)?;
+                let parents = chunk.referents(count)?;
+                hierarchy = children.into_iter().zip(parents).collect();
+            }
+            b"END\0" => break,
+            // META, SSTR and anything newer
+            _ => {}
+        }
+    }
+
+    let mut child_lists: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for (child, parent) in hierarchy {
+        child_lists.entry(parent).or_default().push(child);
+    }
+    let roots = child_lists.get(&-1).cloned().unwrap_or_default();
+    Ok(roots
+        .into_iter()
+        .filter_map(|root| assemble(root, &mut instances, &child_lists))
+        .collect())
+}
+
+/// Move `referent` and its descendants out of `instances` into a tree. Each
+/// instance is taken once, so a malformed cyclic hierarchy still terminates.
+fn assemble(
+    referent: i32,
+    instances: &mut BTreeMap<i32, Instance>,
+    child_lists: &BTreeMap<i32, Vec<i32>>,
+) -> Option<Instance> {
+    let mut instance = instances.remove(&referent)?;
+    for child in child_lists.get(&referent).into_iter().flatten() {
+        instance.children.extend(assemble(*child, instances, child_lists));
+    }
+    Some(instance)
+}
+
+// MARK: - Writing
+
+/// Serialize `roots` and their descendants as a binary model
+pub fn write(roots: &[Instance]) -> Result<Vec<u8>, String> {
+    // Referents are assigned depth-first; roots have parent -1
+    let mut flat: Vec<(&Instance, i32)> = Vec::new();
+    for root in roots {
+        flatten(root, -1, &mut flat);
+    }
+
+    let mut classes: BTreeMap<&str, Vec<i32>> = BTreeMap::new();
+    for (referent, (instance, _)) in flat.iter().enumerate() {
+        classes.entry(&instance.class_name).or_default().push(referent as i32);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&(classes.len() as i32).to_le_bytes());
+    out.extend_from_slice(&(flat.len() as i32).to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+
+    for (class_id, (class_name, referents)) in classes.iter().enumerate() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(class_id as u32).to_le_bytes());
+        write_string(&mut data, class_name.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&(referents.len() as u32).to_le_bytes());
+        write_referents(&mut data, referents);
+        write_chunk(&mut out, b"INST", &data);
+    }
+
+    for (class_id, (class_name, referents)) in classes.iter().enumerate() {
+        let instances: Vec<&Instance> = referents.iter().map(|r| flat[*r as usize].0).collect();
+
+        let mut data = property_header(class_id, "Name", TYPE_STRING);
+        for instance in &instances {
+            write_string(&mut data, instance.name.as_bytes());
+        }
+        write_chunk(&mut out, b"PROP", &data);
+
+        let mut names: Vec<&String> = instances.iter().flat_map(|i| i.properties.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        for name in names {
+            let type_id = instances
+                .iter()
+                .find_map(|i| i.properties.get(name))
+                .map(Value::type_id)
+                .unwrap_or(TYPE_STRING);
+
+            let mut data = property_header(class_id, name, type_id);
+            for instance in &instances {
+                match (instance.properties.get(name), type_id) {
+                    (Some(Value::String(value)), TYPE_STRING) => write_string(&mut data, value.as_bytes()),
+                    (Some(Value::Bytes(value)), TYPE_STRING) => write_string(&mut data, value),
+                    (None, TYPE_STRING) => write_string(&mut data, b""),
+                    (Some(Value::Bool(value)), TYPE_BOOL) => data.push(*value as u8),
+                    (None, TYPE_BOOL) => data.push(0),
+                    _ => {
+                        return Err(format!(
+                            "{}.{} has different types on different instances",
+                            class_name, name
+                        ))
+                    }
+                }
+            }
+            write_chunk(&mut out, b"PROP", &data);
+        }
+    }
+
+    let mut data = vec![0];
+    data.extend_from_slice(&(flat.len() as u32).to_le_bytes());
+    let referents: Vec<i32> = (0..flat.len() as i32).collect();
+    let parents: Vec<i32> = flat.iter().map(|(_, parent)| *parent).collect();
+    write_referents(&mut data, &referents);
+    write_referents(&mut data, &parents);
+    write_chunk(&mut out, b"PRNT", &data);
+
+    write_chunk(&mut out, b"END\0", END_MARKER);
+    Ok(out)
+}
+
+fn flatten<'a>(instance: &'a Instance, parent: i32, flat: &mut Vec<(&'a Instance, i32)>) {
+    let referent = flat.len() as i32;
+    flat.push((instance, parent));
+    for child in &instance.children {
+        flatten(child, referent, flat);
+    }
+}
+
+fn property_header(class_id: usize, name: &str, type_id: u8) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&(class_id as u32).to_le_bytes());
+    write_string(&mut data, name.as_bytes());
+    data.push(type_id);
+    data
+}
+
+/// Chunk header: name, compressed length (0 = uncompressed), length, reserved
+fn write_chunk(out: &mut Vec<u8>, name: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(name);
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(data);
+}
+
+fn write_string(out: &mut Vec<u8>, value: &[u8]) {
+    out.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    out.extend_from_slice(value);
+}
+
+/// Referents are delta-encoded, zigzagged and byte-interleaved big-endian
+fn write_referents(out: &mut Vec<u8>, referents: &[i32]) {
+    let mut last = 0i32;
+    let encoded: Vec<[u8; 4]> = referents
+        .iter()
+        .map(|&referent| {
+            let delta = referent.wrapping_sub(last);
+            last = referent;
+            (((delta << 1) ^ (delta >> 31)) as u32).to_be_bytes()
+        })
+        .collect();
+    for byte in 0..4 {
+        out.extend(encoded.iter().map(|value| value[byte]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk_names(bytes: &[u8]) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut offset = 32;
+        while offset < bytes.len() {
+            names.push(String::from_utf8_lossy(&bytes[offset..offset + 4]).to_string());
+            let length = u32::from_le_bytes(bytes[offset + 8..offset + 12].try_into().unwrap());
+            offset += 16 + length as usize;
+        }
+        names
+    }
+
+    #[test]
+    fn writes_header_and_chunks() {
+        let mut root = Instance::new("Script", "Bakable")
+            .with_property("Source", Value::String("print('hi')".to_string()));
+        root.children.push(Instance::new("Folder", "UI"));
+        root.children.push(
+            Instance::new("ModuleScript", "Theme").with_property("Source", Value::String("return {}".to_string())),
+        );
+
+        let bytes = write(&[root]).unwrap();
+        assert_eq!(&bytes[..14], MAGIC);
+        assert_eq!(i32::from_le_bytes(bytes[16..20].try_into().unwrap()), 3);
+        assert_eq!(i32::from_le_bytes(bytes[20..24].try_into().unwrap()), 3);
+        assert!(bytes.ends_with(END_MARKER));
+
+        // Folder has only Name; the scripts also have Source
+        assert_eq!(
+            chunk_names(&bytes),
+            vec!["INST", "INST", "INST", "PROP", "PROP", "PROP", "PROP", "PROP", "PRNT", "END\0"]
+        );
+    }
+
+    #[test]
+    fn reads_written_and_lz4_models() {
+        let mut ui = Instance::new("Folder", "UI");
+        ui.children.push(Instance::new("ModuleScript", "Theme").with_property("Source", Value::String("return {}".into())));
+        let mut root = Instance::new("Script", "Bakable")
+            .with_property("Source", Value::String("print('hi')".into()))
+            .with_property("Disabled", Value::Bool(false));
+        root.children.push(ui);
+        root.children.push(Instance::new("ModuleScript", "Store").with_property("Source", Value::String("".into())));
+        // Attributes are binary data in a string property
+        root.properties.insert("AttributesSerialize".into(), Value::Bytes(vec![1, 0, 0, 0, 0xff, 0xfe]));
+
+        let bytes = write(std::slice::from_ref(&root)).unwrap();
+        assert_eq!(read(&bytes).unwrap(), vec![root.clone()]);
+
+        // Recompress every chunk the way Rojo and Studio save them
+        let mut compressed = bytes[..32].to_vec();
+        let mut reader = Reader::new(&bytes[32..]);
+        while !reader.is_empty() {
+            let (name, data) = read_chunk(&mut reader).unwrap();
+            let packed = lz4_flex::block::compress(&data);
+            compressed.extend_from_slice(&name);
+            compressed.extend_from_slice(&(packed.len() as u32).to_le_bytes());
+            compressed.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            compressed.extend_from_slice(&[0; 4]);
+            compressed.extend_from_slice(&packed);
+        }
+        assert_eq!(read(&compressed).unwrap(), vec![root.clone()]);
+
+        assert!(read(b"not a model").is_err());
+        assert!(read(&bytes[..bytes.len() / 2]).is_err());
+
+        // A Vector3 on the Folder (class 0) can be skipped but not preserved
+        let (body, end) = bytes.split_at(bytes.len() - 16 - END_MARKER.len());
+        let mut vector = property_header(0, "Position", 0x0E);
+        vector.extend_from_slice(&[0; 12]);
+        let mut with_vector = body.to_vec();
+        write_chunk(&mut with_vector, b"PROP", &vector);
+        with_vector.extend_from_slice(end);
+        assert_eq!(read(&with_vector).unwrap(), vec![root.clone()]);
+        assert!(read_lossless(&with_vector).unwrap_err().contains("Position"));
+        assert_eq!(read_lossless(&bytes).unwrap(), vec![root]);
+    }
+
+    #[test]
+    fn referents_are_delta_zigzag_interleaved() {
+        let mut out = Vec::new();
+        write_referents(&mut out, &[-1, 0, 0]);
+        // deltas -1, 1, 0 zigzag to 1, 2, 0
+        assert_eq!(out, vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 0]);
+    }
+}
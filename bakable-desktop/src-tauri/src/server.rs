@@ -1,96 +1,483 @@
 //! HTTP server for screenshot capture and speech
 //!
+//! Served over TCP on localhost, a Unix domain socket in the app data
+//! directory, or both, depending on the configured transport.
+//!
+//! Endpoints live under `/v1` and report failures with the `api::ApiError`
+//! envelope. The unversioned paths from before `/v1` still work but are
+//! deprecated: responses carry `Deprecation` and a `Link` to the `/v1` path.
+//!
+//! Every endpoint except the OpenAPI document requires
+//! `Authorization: Bearer <token>`, with the token from the discovery file.
+//! `/ws` also takes it as `?token=`.
+//!
 //! Endpoints:
 //! - GET /capture - Capture Roblox Studio viewport, returns PNG
-//! - GET /health - Health check
+//! - GET /health?deep= - Plugin, Studio, daemon and session health
 //! - GET /permission - Check/request screen capture permission
 //! - POST /speech/listen - Start speech recognition
 //! - POST /speech/stop - Stop speech recognition
 //! - GET /speech/transcription - Get current transcription
 //! - POST /speech/speak - Text-to-speech
 //! - POST /speech/silence - Stop speaking
+//! - GET /plugin/status - Installed vs bundled plugin version
+//! - POST /plugin/uninstall - Remove the Bakable plugin
+//! - GET /plugin/dirs - Candidate plugins directories and the chosen one
+//! - GET /plugin/diff - Scripts that differ between installed and bundled plugin
+//! - GET /plugins - Every plugin file, enabled and disabled
+//! - POST /plugins/disable - Move a plugin to `Plugins.disabled`
+//! - POST /plugins/enable - Move a disabled plugin back
+//! - POST /plugins/import - Copy a plugin file into the plugins directory
+//! - GET /ws - WebSocket stream of helper events (see `events::Event`)
+//! - POST /mcp - MCP streamable HTTP transport (see `mcp`)
+//! - GET /metrics - Prometheus metrics
+//! - GET /logs?since=&level= - Recent helper log entries
+//! - GET /v1/openapi.json - OpenAPI document (see `openapi`)
 
 use axum::{
-    extract::Query,
-    http::{header, StatusCode},
+    extract::{
+        rejection::{JsonRejection, QueryRejection},
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        MatchedPath, Query, Request, State,
+    },
+    http::{header, HeaderValue},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+#[cfg(unix)]
+use std::path::Path;
 use tauri::AppHandle;
-use tower_http::cors::{Any, CorsLayer};
-use tracing::{info, error};
+use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::sync::broadcast::error::RecvError;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::{info, warn, Instrument, Level};
+
+use crate::events::{self, Event};
+use crate::api::ApiError;
+use crate::config::{self, Config};
+use crate::daemon::{self, DaemonState};
+use crate::plugin::{PluginChannel, PluginDiff, PluginStatus, PluginsDirResolution};
+use crate::plugin_manager::ManagedPlugin;
+use crate::state::{AppState, ListenInfo};
+use crate::{logging, mcp, metrics, openapi, shutdown};
+
+/// Origins that may call the helper from a browser context: the Tauri
+/// webview (macOS/Linux and Windows schemes) and the Vite dev server
+const DEFAULT_ALLOWED_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "https://tauri.localhost",
+    "http://localhost:1420",
+    "http://127.0.0.1:1420",
+];
+
+/// Comma-separated list of additional allowed origins
+const ALLOWED_ORIGINS_ENV: &str = "BAKABLE_ALLOWED_ORIGINS";
+
+/// Browser origins allowed to reach the helper.
+///
+/// Requests without an `Origin` header (the daemon, CLI, curl) are always
+/// accepted; anything a browser sends from another origin is rejected.
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    allowed_origins: Vec<HeaderValue>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: DEFAULT_ALLOWED_ORIGINS
+                .iter()
+                .map(|origin| HeaderValue::from_static(origin))
+                .collect(),
+        }
+    }
+}
 
-use crate::{capture, speech};
+impl CorsConfig {
+    /// Default origins plus any listed in `BAKABLE_ALLOWED_ORIGINS`
+    pub fn from_env() -> Self {
+        let extra = std::env::var(ALLOWED_ORIGINS_ENV).unwrap_or_default();
+        Self::default().with_origins(extra.split(','))
+    }
+
+    /// Add origins to the allowlist, skipping blank or malformed entries
+    pub fn with_origins<'a>(mut self, origins: impl IntoIterator<Item = &'a str>) -> Self {
+        for origin in origins {
+            let origin = origin.trim().trim_end_matches('/');
+            if origin.is_empty() {
+                continue;
+            }
+            match HeaderValue::from_str(origin) {
+                Ok(value) if !self.allowed_origins.contains(&value) => {
+                    self.allowed_origins.push(value)
+                }
+                Ok(_) => {}
+                Err(_) => warn!("Ignoring invalid allowed origin: {:?}", origin),
+            }
+        }
+        self
+    }
 
-const PORT: u16 = 4850;
+    fn is_allowed(&self, origin: &HeaderValue) -> bool {
+        self.allowed_origins.contains(origin)
+    }
 
-#[derive(Debug, Serialize)]
-struct HealthResponse {
+    fn layer(&self) -> CorsLayer {
+        CorsLayer::new()
+            .allow_origin(AllowOrigin::list(self.allowed_origins.clone()))
+            .allow_methods(Any)
+            .allow_headers(Any)
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct HealthQuery {
+    /// `1` to actively probe each dependency instead of reporting cached state
+    deep: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct HealthResponse {
+    /// `ok`, or `degraded` when the plugin or daemon needs attention
     status: &'static str,
     version: &'static str,
+    uptime_secs: u64,
+    listen: ListenInfo,
     has_capture_permission: bool,
     has_speech_permission: bool,
+    plugin: PluginHealth,
+    studio: StudioHealth,
+    snap_enabled: bool,
+    daemon: DaemonHealth,
+    sessions: SessionHealth,
+    /// Only present for `?deep=1`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    probes: Option<Vec<Probe>>,
 }
 
-#[derive(Debug, Serialize)]
-struct PermissionResponse {
-    granted: bool,
-    message: &'static str,
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct PluginHealth {
+    channel: PluginChannel,
+    installed: bool,
+    /// Installed plugin matches the selected channel's build
+    up_to_date: bool,
+    path: Option<PathBuf>,
+    version: Option<String>,
+}
+
+impl From<PluginStatus> for PluginHealth {
+    fn from(status: PluginStatus) -> Self {
+        Self {
+            channel: status.channel,
+            installed: status.installed,
+            up_to_date: status.up_to_date(),
+            path: status.path,
+            version: status.installed_version,
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
-struct CaptureError {
-    error: String,
-    code: &'static str,
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct StudioHealth {
+    window_found: bool,
+    focused: bool,
 }
 
-#[derive(Debug, Serialize)]
-struct CaptureBase64Response {
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct DaemonHealth {
+    state: DaemonState,
+    port: u16,
+    /// From the supervisor's state, or from a connection attempt with `?deep=1`
+    reachable: bool,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct SessionHealth {
+    listening: bool,
+    speaking: bool,
+}
+
+/// Result of actively checking one dependency
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct Probe {
+    name: &'static str,
+    ok: bool,
+    duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct PermissionResponse {
+    granted: bool,
+    message: &'static str,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct CaptureBase64Response {
     base64: String,
     media_type: &'static str,
 }
 
-#[derive(Debug, Deserialize)]
-struct CaptureQuery {
-    width: Option<u32>,
-    format: Option<String>,
+/// Parameters for `/capture`, also the input schema of the MCP capture tool
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+pub(crate) struct CaptureQuery {
+    /// `base64` to return JSON with base64-encoded PNG instead of raw bytes
+    pub format: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-struct SpeechStatus {
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct SpeechStatus {
     listening: bool,
     speaking: bool,
     has_permission: bool,
 }
 
-#[derive(Debug, Serialize)]
-struct TranscriptionResponse {
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct TranscriptionResponse {
     text: Option<String>,
     listening: bool,
 }
 
-#[derive(Debug, Deserialize)]
-struct SpeakRequest {
-    text: String,
+/// Body of `/speech/speak`, also the input schema of the MCP speak tool
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct SpeakRequest {
+    /// Text to speak aloud
+    pub text: String,
+}
+
+/// Body of `/plugins/disable` and `/plugins/enable`
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct PluginNameRequest {
+    /// Plugin file name, e.g. `Tool.rbxm`
+    pub name: String,
 }
 
-#[derive(Debug, Serialize)]
-struct GenericResponse {
+/// Body of `/plugins/import`
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct ImportPluginRequest {
+    /// Path of the `.rbxm`, `.rbxmx` or `.lua` file to copy
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct PluginListResponse {
+    plugins: Vec<ManagedPlugin>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct GenericResponse {
     success: bool,
     message: String,
 }
 
-/// Start the HTTP server for screenshot capture and speech
-pub async fn start_capture_server(_app: AppHandle) -> Result<(), Box<dyn std::error::Error>> {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+/// Bind the helper's listener on localhost, falling back to a free port if
+/// `port` is already taken
+pub async fn bind_listener(port: u16) -> std::io::Result<TcpListener> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+
+    match TcpListener::bind(addr).await {
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+            warn!("Port {} is in use, falling back to a free port", port);
+            TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0))).await
+        }
+        result => result,
+    }
+}
+
+/// Bind the helper's Unix domain socket at `path`, readable and writable
+/// only by the current user. A stale socket left by a crashed helper is
+/// replaced; one that still accepts connections is an error.
+///
+/// The parent directory is made private first, since the socket is created
+/// with the process umask and only narrowed to 0600 after binding.
+#[cfg(unix)]
+pub async fn bind_unix_listener(path: &Path) -> std::io::Result<UnixListener> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if path.exists() {
+        if tokio::net::UnixStream::connect(path).await.is_ok() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("{} is in use by another helper", path.display()),
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    if let Some(dir) = path.parent() {
+        std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+        std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Listeners chosen by the configured transport
+pub struct Listeners {
+    tcp: Option<TcpListener>,
+    #[cfg(unix)]
+    unix: Option<(UnixListener, PathBuf)>,
+}
+
+impl Listeners {
+    /// Bind the listeners `config` asks for
+    pub async fn bind(config: &Config) -> std::io::Result<Self> {
+        let transport = config.transport();
+
+        let tcp = match transport.uses_tcp() {
+            true => Some(bind_listener(config.port()).await?),
+            false => None,
+        };
+
+        #[cfg(unix)]
+        let unix = match transport.uses_unix() {
+            true => {
+                let path = config::socket_path().ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "Could not determine app data directory",
+                    )
+                })?;
+                Some((bind_unix_listener(&path).await?, path))
+            }
+            false => None,
+        };
+
+        Ok(Self {
+            tcp,
+            #[cfg(unix)]
+            unix,
+        })
+    }
+
+    /// Bound TCP port, if listening on TCP
+    pub fn port(&self) -> Option<u16> {
+        self.tcp
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
+            .map(|addr| addr.port())
+    }
+
+    /// Socket path, if listening on a Unix domain socket
+    pub fn socket(&self) -> Option<PathBuf> {
+        #[cfg(unix)]
+        return self.unix.as_ref().map(|(_, path)| path.clone());
+
+        #[cfg(not(unix))]
+        None
+    }
+}
+
+/// Start the HTTP server for screenshot capture and speech on every bound
+/// listener. Returns once shutdown has started and in-flight requests have
+/// completed.
+pub async fn start_capture_server(
+    _app: AppHandle,
+    listeners: Listeners,
+    mut state: AppState,
+    cors: CorsConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    state.listen = ListenInfo {
+        port: listeners.port(),
+        socket: listeners.socket(),
+    };
+    let app = build_router(state, cors);
+
+    let tcp = async {
+        let Some(listener) = listeners.tcp else { return Ok(()) };
+        info!("Starting capture server on http://{}", listener.local_addr()?);
+        axum::serve(listener, app.clone())
+            .with_graceful_shutdown(shutdown::wait())
+            .await
+    };
+
+    #[cfg(unix)]
+    let unix = async {
+        let Some((listener, path)) = listeners.unix else { return Ok(()) };
+        info!("Starting capture server on unix:{}", path.display());
+        let result = serve_unix(listener, app.clone()).await;
+        let _ = std::fs::remove_file(&path);
+        result
+    };
 
-    let app = Router::new()
+    #[cfg(not(unix))]
+    let unix = async { Ok(()) };
+
+    tokio::try_join!(tcp, unix)?;
+    Ok(())
+}
+
+/// Serve `app` on a Unix socket until shutdown, then let open connections
+/// finish. `axum::serve` only accepts TCP listeners, so connections are
+/// driven with hyper directly.
+#[cfg(unix)]
+async fn serve_unix(listener: UnixListener, app: Router) -> std::io::Result<()> {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::server::conn::auto::Builder;
+    use hyper_util::server::graceful::GracefulShutdown;
+    use hyper_util::service::TowerToHyperService;
+
+    let builder = Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+
+    loop {
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("Failed to accept socket connection: {}", e);
+                    continue;
+                }
+            },
+            _ = shutdown::wait() => break,
+        };
+
+        let service = TowerToHyperService::new(app.clone());
+        let connection = builder
+            .serve_connection_with_upgrades(TokioIo::new(stream), service)
+            .into_owned();
+        let connection = graceful.watch(connection);
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                warn!("Socket connection error: {}", e);
+            }
+        });
+    }
+
+    graceful.shutdown().await;
+    Ok(())
+}
+
+/// Build the helper router over `state` with the given origin allowlist
+pub fn build_router(state: AppState, cors: CorsConfig) -> Router {
+    Router::new()
+        .nest("/v1", api_routes(&state).route("/openapi.json", get(openapi_handler)))
+        // Paths from before /v1, kept as deprecated aliases
+        .merge(api_routes(&state).route_layer(middleware::from_fn(deprecated_alias)))
+        .fallback(not_found)
+        .route_layer(middleware::from_fn_with_state(state.clone(), track_metrics))
+        .layer(cors.layer())
+        // Outermost, so foreign preflights are refused before CorsLayer answers them
+        .layer(middleware::from_fn_with_state(cors, reject_foreign_origin))
+        .layer(middleware::from_fn(request_span))
+        .with_state(state)
+}
+
+/// Routes served under `/v1` and, deprecated, at the root.
+/// Keep `openapi::OPERATIONS` in sync when adding routes.
+fn api_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
         // Capture endpoints
         .route("/health", get(health_handler))
         .route("/permission", get(permission_handler))
@@ -102,36 +489,239 @@ pub async fn start_capture_server(_app: AppHandle) -> Result<(), Box<dyn std::er
         .route("/speech/transcription", get(transcription_handler))
         .route("/speech/speak", post(speak_handler))
         .route("/speech/silence", post(silence_handler))
-        .layer(cors);
+        // Studio plugin
+        .route("/plugin/status", get(plugin_status_handler))
+        .route("/plugin/uninstall", post(plugin_uninstall_handler))
+        .route("/plugin/dirs", get(plugin_dirs_handler))
+        .route("/plugin/diff", get(plugin_diff_handler))
+        .route("/plugins", get(plugins_handler))
+        .route("/plugins/disable", post(disable_plugin_handler))
+        .route("/plugins/enable", post(enable_plugin_handler))
+        .route("/plugins/import", post(import_plugin_handler))
+        // Event stream
+        .route("/ws", get(ws_handler))
+        // MCP tools
+        .route("/mcp", post(mcp::http_handler))
+        // Metrics
+        .route("/metrics", get(metrics_handler))
+        // Logs
+        .route("/logs", get(logs_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+}
+
+/// Reject requests without the state's bearer token. WebSocket upgrades may
+/// pass it as `?token=` instead, since browsers can't set the header on them.
+async fn require_token(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| websocket_query_token(&request));
+
+    match token {
+        Some(token) if tokens_match(token.trim(), &state.token) => next.run(request).await,
+        Some(_) => ApiError::unauthorized("Invalid bearer token").into_response(),
+        None => ApiError::unauthorized("Missing bearer token").into_response(),
+    }
+}
+
+/// `token` query parameter of a WebSocket upgrade request
+fn websocket_query_token(request: &Request) -> Option<&str> {
+    let upgrade = request
+        .headers()
+        .get(header::UPGRADE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+    if !upgrade {
+        return None;
+    }
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+}
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], PORT));
-    info!("Starting capture server on http://{}", addr);
+/// Compare without stopping at the first differing byte
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+/// Mark a response from an unversioned path as deprecated in favour of `/v1`
+async fn deprecated_alias(request: Request, next: Next) -> Response {
+    let successor = format!("</v1{}>; rel=\"successor-version\"", request.uri().path());
 
-    Ok(())
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&successor) {
+        headers.insert(header::LINK, link);
+    }
+    response
+}
+
+async fn not_found(request: Request) -> ApiError {
+    ApiError::not_found(format!("No route for {} {}", request.method(), request.uri().path()))
+}
+
+/// Run each request inside a `request` span with a unique ID, echoed back in
+/// the `x-request-id` header so log entries can be matched to requests
+async fn request_span(request: Request, next: Next) -> Response {
+    let id = logging::next_request_id();
+    let span = tracing::info_span!(
+        "request",
+        id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let mut response = next.run(request).instrument(span).await;
+    response
+        .headers_mut()
+        .insert("x-request-id", HeaderValue::from(id));
+    response
+}
+
+/// Count requests and record latency per matched route
+async fn track_metrics(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let started = state.clock.now();
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::HTTP_REQUESTS.inc(&[("method", &method), ("route", &route), ("status", &status)]);
+    metrics::HTTP_DURATION.observe_duration(&[("method", &method), ("route", &route)], state.clock.now() - started);
+
+    response
+}
+
+/// Reject browser requests (including preflights) from origins not in the allowlist
+async fn reject_foreign_origin(
+    State(cors): State<CorsConfig>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(origin) = request.headers().get(header::ORIGIN) {
+        if !cors.is_allowed(origin) {
+            warn!("Rejected request from origin {:?}", origin);
+            return ApiError::forbidden("ORIGIN_NOT_ALLOWED", "Origin not allowed").into_response();
+        }
+    }
+
+    next.run(request).await
 }
 
 // MARK: - Capture Handlers
 
-async fn health_handler() -> Json<HealthResponse> {
-    Json(HealthResponse {
+/// Upper bound on each `?deep=1` probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub(crate) async fn health_handler(
+    State(state): State<AppState>,
+    query: Result<Query<HealthQuery>, QueryRejection>,
+) -> Result<Json<HealthResponse>, ApiError> {
+    let Query(query) = query?;
+    let deep = matches!(query.deep.as_deref(), Some("1" | "true"));
+
+    let daemon_state = state.daemon.state();
+    let mut health = HealthResponse {
         status: "ok",
         version: env!("CARGO_PKG_VERSION"),
-        has_capture_permission: capture::has_capture_permission(),
-        has_speech_permission: speech::has_speech_permission(),
-    })
+        uptime_secs: state.uptime().as_secs(),
+        listen: state.listen.clone(),
+        has_capture_permission: state.capture.has_permission(),
+        has_speech_permission: state.speech.has_permission(),
+        plugin: PluginHealth::from(state.plugin.status()),
+        studio: StudioHealth {
+            window_found: state.capture.window_id().is_some(),
+            focused: state.desktop.is_studio_focused(),
+        },
+        snap_enabled: state.desktop.is_snap_enabled(),
+        daemon: DaemonHealth {
+            reachable: matches!(daemon_state, DaemonState::Running { .. } | DaemonState::External),
+            state: daemon_state,
+            port: daemon::DAEMON_PORT,
+        },
+        sessions: SessionHealth {
+            listening: state.speech.is_listening(),
+            speaking: state.speech.is_speaking(),
+        },
+        probes: None,
+    };
+
+    if deep {
+        let probes = run_probes(&state).await;
+        for probe in &probes {
+            match probe.name {
+                "daemon" => health.daemon.reachable = probe.ok,
+                "plugin" => health.plugin.up_to_date = probe.ok,
+                "studio" => health.studio.window_found = probe.ok,
+                _ => {}
+            }
+        }
+        health.probes = Some(probes);
+    }
+
+    // Studio not running is normal; only things the helper can fix degrade
+    if !health.plugin.up_to_date || !health.daemon.reachable {
+        health.status = "degraded";
+    }
+    Ok(Json(health))
+}
+
+/// Check the daemon port, the installed plugin and the Studio window
+/// concurrently, each bounded by `PROBE_TIMEOUT`
+async fn run_probes(state: &AppState) -> Vec<Probe> {
+    let daemon = state.daemon.probe(PROBE_TIMEOUT);
+    let plugin = state.plugin.clone();
+    let capture = state.capture.clone();
+
+    let (daemon, plugin, studio) = tokio::join!(
+        probe("daemon", async move { Ok::<_, std::convert::Infallible>(daemon.await) }),
+        probe("plugin", tokio::task::spawn_blocking(move || plugin.status().up_to_date())),
+        probe("studio", tokio::task::spawn_blocking(move || capture.window_id().is_some())),
+    );
+    vec![daemon, plugin, studio]
+}
+
+async fn probe<E: std::fmt::Display>(
+    name: &'static str,
+    check: impl std::future::Future<Output = Result<bool, E>>,
+) -> Probe {
+    let started = Instant::now();
+    let (ok, error) = match tokio::time::timeout(PROBE_TIMEOUT, check).await {
+        Ok(Ok(ok)) => (ok, None),
+        Ok(Err(e)) => (false, Some(e.to_string())),
+        Err(_) => (false, Some(format!("timed out after {}ms", PROBE_TIMEOUT.as_millis()))),
+    };
+    Probe {
+        name,
+        ok,
+        duration_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
 }
 
-async fn permission_handler() -> Json<PermissionResponse> {
-    if capture::has_capture_permission() {
+pub(crate) async fn permission_handler(State(state): State<AppState>) -> Json<PermissionResponse> {
+    if state.capture.has_permission() {
         Json(PermissionResponse {
             granted: true,
             message: "Screen capture permission granted",
         })
     } else {
-        capture::request_permission();
+        state.capture.request_permission();
         Json(PermissionResponse {
             granted: false,
             message: "Permission requested. Please grant access in System Settings > Privacy > Screen Recording",
@@ -139,117 +729,755 @@ async fn permission_handler() -> Json<PermissionResponse> {
     }
 }
 
-async fn capture_handler(Query(params): Query<CaptureQuery>) -> Response {
+async fn capture_handler(
+    State(state): State<AppState>,
+    query: Result<Query<CaptureQuery>, QueryRejection>,
+) -> Result<Response, ApiError> {
     use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
-    if !capture::has_capture_permission() {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(CaptureError {
-                error: "Screen capture permission not granted. Visit /permission to request.".to_string(),
-                code: "PERMISSION_DENIED",
-            }),
-        )
-            .into_response();
-    }
-
-    match capture::capture_studio_viewport() {
-        Some(png_data) => {
-            info!("Screenshot captured: {} bytes", png_data.len());
-
-            // Return base64 JSON if format=base64 requested
-            if params.format.as_deref() == Some("base64") {
-                let base64_data = BASE64.encode(&png_data);
-                return Json(CaptureBase64Response {
-                    base64: base64_data,
-                    media_type: "image/png",
-                }).into_response();
-            }
+    let Query(params) = query?;
 
-            // Default: return raw PNG
-            (
-                StatusCode::OK,
-                [(header::CONTENT_TYPE, "image/png")],
-                png_data,
-            )
-                .into_response()
-        }
-        None => (
-            StatusCode::SERVICE_UNAVAILABLE,
-            Json(CaptureError {
-                error: "Failed to capture Roblox Studio. Is it running?".to_string(),
-                code: "CAPTURE_FAILED",
-            }),
-        )
-            .into_response(),
+    if !state.capture.has_permission() {
+        metrics::CAPTURE_FAILURES.inc(&[("code", "PERMISSION_DENIED")]);
+        return Err(ApiError::forbidden(
+            "PERMISSION_DENIED",
+            "Screen capture permission not granted. Visit /v1/permission to request.",
+        ));
+    }
+
+    let png_data = state.capture.capture_viewport().ok_or_else(|| {
+        ApiError::unavailable("CAPTURE_FAILED", "Failed to capture Roblox Studio. Is it running?")
+    })?;
+    info!("Screenshot captured: {} bytes", png_data.len());
+
+    // Return base64 JSON if format=base64 requested
+    if params.format.as_deref() == Some("base64") {
+        let base64_data = BASE64.encode(&png_data);
+        return Ok(Json(CaptureBase64Response {
+            base64: base64_data,
+            media_type: "image/png",
+        })
+        .into_response());
     }
+
+    // Default: return raw PNG
+    Ok(([(header::CONTENT_TYPE, "image/png")], png_data).into_response())
 }
 
 // MARK: - Speech Handlers
 
-async fn speech_status_handler() -> Json<SpeechStatus> {
+pub(crate) async fn speech_status_handler(State(state): State<AppState>) -> Json<SpeechStatus> {
     Json(SpeechStatus {
-        listening: speech::is_currently_listening(),
-        speaking: speech::is_currently_speaking(),
-        has_permission: speech::has_speech_permission(),
+        listening: state.speech.is_listening(),
+        speaking: state.speech.is_speaking(),
+        has_permission: state.speech.has_permission(),
     })
 }
 
-async fn start_listen_handler() -> Json<GenericResponse> {
-    if !speech::has_speech_permission() {
-        speech::request_stt_permission();
-        return Json(GenericResponse {
-            success: false,
-            message: "Speech permission not granted. Please grant in System Settings > Privacy > Speech Recognition".to_string(),
-        });
+pub(crate) async fn start_listen_handler(
+    State(state): State<AppState>,
+) -> Result<Json<GenericResponse>, ApiError> {
+    if !state.speech.has_permission() {
+        state.speech.request_permission();
+        return Err(ApiError::forbidden(
+            "PERMISSION_DENIED",
+            "Speech permission not granted. Please grant in System Settings > Privacy > Speech Recognition",
+        ));
     }
 
-    if speech::start_listening() {
-        Json(GenericResponse {
-            success: true,
-            message: "Started listening".to_string(),
-        })
-    } else {
-        Json(GenericResponse {
-            success: false,
-            message: "Failed to start speech recognition".to_string(),
-        })
+    if !state.speech.start_listening() {
+        return Err(ApiError::unavailable(
+            "RECOGNITION_FAILED",
+            "Failed to start speech recognition",
+        ));
     }
+
+    Ok(Json(GenericResponse {
+        success: true,
+        message: "Started listening".to_string(),
+    }))
 }
 
-async fn stop_listen_handler() -> Json<GenericResponse> {
-    speech::stop_listening();
+pub(crate) async fn stop_listen_handler(State(state): State<AppState>) -> Json<GenericResponse> {
+    state.speech.stop_listening();
     Json(GenericResponse {
         success: true,
         message: "Stopped listening".to_string(),
     })
 }
 
-async fn transcription_handler() -> Json<TranscriptionResponse> {
+pub(crate) async fn transcription_handler(
+    State(state): State<AppState>,
+) -> Json<TranscriptionResponse> {
     Json(TranscriptionResponse {
-        text: speech::get_current_transcription(),
-        listening: speech::is_currently_listening(),
+        text: state.speech.transcription(),
+        listening: state.speech.is_listening(),
     })
 }
 
-async fn speak_handler(Json(payload): Json<SpeakRequest>) -> Json<GenericResponse> {
-    if speech::speak(&payload.text) {
-        Json(GenericResponse {
-            success: true,
-            message: "Speaking".to_string(),
-        })
-    } else {
-        Json(GenericResponse {
-            success: false,
-            message: "Failed to speak".to_string(),
-        })
+pub(crate) async fn speak_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<SpeakRequest>, JsonRejection>,
+) -> Result<Json<GenericResponse>, ApiError> {
+    let Json(payload) = payload?;
+
+    if payload.text.trim().is_empty() {
+        return Err(ApiError::bad_request("INVALID_BODY", "text must not be empty")
+            .with_details(serde_json::json!({ "field": "text" })));
     }
+
+    if !state.speech.speak(&payload.text) {
+        return Err(ApiError::unavailable("SPEECH_FAILED", "Failed to speak"));
+    }
+
+    Ok(Json(GenericResponse {
+        success: true,
+        message: "Speaking".to_string(),
+    }))
 }
 
-async fn silence_handler() -> Json<GenericResponse> {
-    speech::stop_tts();
+pub(crate) async fn silence_handler(State(state): State<AppState>) -> Json<GenericResponse> {
+    state.speech.stop_speaking();
     Json(GenericResponse {
         success: true,
         message: "Stopped speaking".to_string(),
     })
 }
+
+// MARK: - Plugin
+
+pub(crate) async fn plugin_status_handler(State(state): State<AppState>) -> Json<PluginStatus> {
+    Json(state.plugin.status())
+}
+
+pub(crate) async fn plugin_uninstall_handler(
+    State(state): State<AppState>,
+) -> Result<Json<GenericResponse>, ApiError> {
+    let removed = state
+        .plugin
+        .uninstall()
+        .map_err(|e| ApiError::unavailable("UNINSTALL_FAILED", e))?;
+    Ok(Json(GenericResponse {
+        success: true,
+        message: match removed {
+            true => "Plugin uninstalled".to_string(),
+            false => "Plugin was not installed".to_string(),
+        },
+    }))
+}
+
+pub(crate) async fn plugin_diff_handler(State(state): State<AppState>) -> Result<Json<PluginDiff>, ApiError> {
+    // Reads and parses both plugin files
+    tokio::task::spawn_blocking(move || state.plugin.diff())
+        .await
+        .map(Json)
+        .map_err(|e| ApiError::unavailable("PLUGIN_DIFF_FAILED", format!("Plugin diff failed: {}", e)))
+}
+
+pub(crate) async fn plugin_dirs_handler(State(state): State<AppState>) -> Json<PluginsDirResolution> {
+    Json(state.plugin.dirs())
+}
+
+pub(crate) async fn plugins_handler(
+    State(state): State<AppState>,
+) -> Result<Json<PluginListResponse>, ApiError> {
+    let plugins = state
+        .plugins
+        .list()
+        .map_err(|e| ApiError::unavailable("PLUGINS_DIR_UNAVAILABLE", e))?;
+    Ok(Json(PluginListResponse { plugins }))
+}
+
+fn set_plugin_enabled(
+    state: AppState,
+    payload: Result<Json<PluginNameRequest>, JsonRejection>,
+    enabled: bool,
+) -> Result<Json<ManagedPlugin>, ApiError> {
+    let Json(payload) = payload?;
+    state
+        .plugins
+        .set_enabled(&payload.name, enabled)
+        .map(Json)
+        .map_err(|e| {
+            ApiError::bad_request("PLUGIN_NOT_MOVED", e)
+                .with_details(serde_json::json!({ "name": payload.name }))
+        })
+}
+
+pub(crate) async fn disable_plugin_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<PluginNameRequest>, JsonRejection>,
+) -> Result<Json<ManagedPlugin>, ApiError> {
+    set_plugin_enabled(state, payload, false)
+}
+
+pub(crate) async fn enable_plugin_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<PluginNameRequest>, JsonRejection>,
+) -> Result<Json<ManagedPlugin>, ApiError> {
+    set_plugin_enabled(state, payload, true)
+}
+
+pub(crate) async fn import_plugin_handler(
+    State(state): State<AppState>,
+    payload: Result<Json<ImportPluginRequest>, JsonRejection>,
+) -> Result<Json<ManagedPlugin>, ApiError> {
+    let Json(payload) = payload?;
+    state
+        .plugins
+        .import(&payload.path)
+        .map(Json)
+        .map_err(|e| ApiError::bad_request("PLUGIN_NOT_IMPORTED", e))
+}
+
+// MARK: - Metrics
+
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics::render(),
+    )
+}
+
+// MARK: - Logs
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub(crate) struct LogsQuery {
+    /// Only entries after this Unix time in milliseconds
+    since: Option<u64>,
+    /// Minimum level, e.g. `warn`
+    level: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct LogsResponse {
+    entries: Vec<logging::LogEntry>,
+}
+
+async fn logs_handler(
+    query: Result<Query<LogsQuery>, QueryRejection>,
+) -> Result<Json<LogsResponse>, ApiError> {
+    let Query(params) = query?;
+
+    let level = params
+        .level
+        .as_deref()
+        .map(|level| {
+            level.parse::<Level>().map_err(|_| {
+                ApiError::bad_request(
+                    "INVALID_QUERY",
+                    "level must be one of error, warn, info, debug, trace",
+                )
+                .with_details(serde_json::json!({ "field": "level" }))
+            })
+        })
+        .transpose()?;
+
+    Ok(Json(LogsResponse {
+        entries: logging::recent_logs(params.since, level),
+    }))
+}
+
+// MARK: - OpenAPI
+
+async fn openapi_handler() -> Json<serde_json::Value> {
+    Json(openapi::document())
+}
+
+// MARK: - Event Stream
+
+async fn ws_handler(ws: WebSocketUpgrade) -> Response {
+    ws.on_upgrade(stream_events)
+}
+
+/// Forward bus events to the socket as JSON text frames until either side
+/// closes or the app shuts down
+async fn stream_events(mut socket: WebSocket) {
+    let mut events = events::subscribe();
+
+    loop {
+        tokio::select! {
+            _ = shutdown::wait() => {
+                let _ = socket.send(Message::Close(None)).await;
+                break;
+            }
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => Event::Lagged { skipped },
+                    Err(RecvError::Closed) => break,
+                };
+                let Ok(json) = serde_json::to_string(&event) else { continue };
+                if socket.send(Message::Text(json)).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Clients don't send anything meaningful; pings are answered by axum
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::fakes::{Fakes, TOKEN};
+    use axum::{
+        body::Body,
+        http::{Method, StatusCode},
+    };
+    use std::sync::atomic::Ordering;
+    use tower::ServiceExt;
+
+    fn router(cors: CorsConfig) -> Router {
+        build_router(Fakes::default().state(), cors)
+    }
+
+    fn authorized(uri: &str) -> Request {
+        Request::builder()
+            .uri(uri)
+            .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    fn preflight(origin: &str) -> Request {
+        Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/capture")
+            .header(header::ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    async fn allowed_origin(app: Router, request: Request) -> (StatusCode, Option<HeaderValue>) {
+        let response = app.oneshot(request).await.unwrap();
+        let allow = response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .cloned();
+        (response.status(), allow)
+    }
+
+    #[tokio::test]
+    async fn preflight_from_tauri_webview_is_allowed() {
+        let (status, allow) =
+            allowed_origin(router(CorsConfig::default()), preflight("tauri://localhost")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(allow.unwrap(), "tauri://localhost");
+    }
+
+    #[tokio::test]
+    async fn preflight_from_dev_server_is_allowed() {
+        let (status, allow) =
+            allowed_origin(router(CorsConfig::default()), preflight("http://localhost:1420")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(allow.unwrap(), "http://localhost:1420");
+    }
+
+    #[tokio::test]
+    async fn preflight_from_unknown_origin_is_rejected() {
+        let (status, allow) =
+            allowed_origin(router(CorsConfig::default()), preflight("https://evil.example")).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(allow.is_none());
+    }
+
+    #[tokio::test]
+    async fn request_from_unknown_origin_is_rejected() {
+        let request = Request::builder()
+            .uri("/permission")
+            .header(header::ORIGIN, "http://localhost:3000")
+            .body(Body::empty())
+            .unwrap();
+        let (status, allow) = allowed_origin(router(CorsConfig::default()), request).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(allow.is_none());
+    }
+
+    #[tokio::test]
+    async fn requests_without_the_token_are_rejected() {
+        for (request, message) in [
+            (Request::builder().uri("/v1/permission"), "Missing bearer token"),
+            (
+                Request::builder().method("POST").uri("/v1/plugins/import"),
+                "Missing bearer token",
+            ),
+            (
+                Request::builder().uri("/permission").header(header::AUTHORIZATION, "Bearer nope"),
+                "Invalid bearer token",
+            ),
+        ] {
+            let response = router(CorsConfig::default())
+                .oneshot(request.body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(json["code"], "UNAUTHORIZED");
+            assert_eq!(json["message"], message);
+        }
+
+        let response = router(CorsConfig::default())
+            .oneshot(Request::builder().uri("/v1/openapi.json").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn websocket_upgrades_take_the_token_as_a_query_parameter() {
+        let upgrade = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .header(header::CONNECTION, "upgrade")
+                .header(header::UPGRADE, "websocket")
+                .header(header::SEC_WEBSOCKET_VERSION, "13")
+                .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        for (uri, status) in [
+            (format!("/v1/ws?token={}", TOKEN), StatusCode::UPGRADE_REQUIRED),
+            ("/v1/ws?token=nope".to_string(), StatusCode::UNAUTHORIZED),
+            ("/v1/ws".to_string(), StatusCode::UNAUTHORIZED),
+        ] {
+            // Past auth, oneshot has no connection to upgrade
+            let response = router(CorsConfig::default()).oneshot(upgrade(&uri)).await.unwrap();
+            assert_eq!(response.status(), status, "{}", uri);
+        }
+
+        let response = router(CorsConfig::default())
+            .oneshot(Request::builder().uri(format!("/v1/capture?token={}", TOKEN)).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn metrics_count_requests_by_route() {
+        let request = || authorized("/metrics");
+        router(CorsConfig::default()).oneshot(request()).await.unwrap();
+
+        let response = router(CorsConfig::default()).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("bakable_http_requests_total{method=\"GET\",route=\"/metrics\",status=\"200\"}"));
+        assert!(body.contains("# TYPE bakable_capture_duration_seconds histogram"));
+    }
+
+    #[tokio::test]
+    async fn configured_origin_is_allowed() {
+        let cors = CorsConfig::default().with_origins(" https://studio.example/ ,,".split(','));
+        let (status, allow) = allowed_origin(router(cors), preflight("https://studio.example")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(allow.unwrap(), "https://studio.example");
+    }
+
+    async fn send(app: Router, method: Method, uri: &str, body: &str) -> (StatusCode, Vec<u8>) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::AUTHORIZATION, format!("Bearer {}", TOKEN))
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, body.to_vec())
+    }
+
+    async fn send_json(app: Router, method: Method, uri: &str, body: &str) -> (StatusCode, serde_json::Value) {
+        let (status, body) = send(app, method, uri, body).await;
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn health_reports_dependencies() {
+        let fakes = Fakes::default();
+        fakes.capture.permission.store(true, Ordering::SeqCst);
+        fakes.speech.listening.store(true, Ordering::SeqCst);
+        fakes.desktop.snap_enabled.store(true, Ordering::SeqCst);
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, health) = send_json(app.clone(), Method::GET, "/v1/health", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health["status"], "degraded");
+        assert_eq!(health["has_capture_permission"], true);
+        assert_eq!(health["has_speech_permission"], false);
+        assert_eq!(health["plugin"]["installed"], false);
+        assert_eq!(health["studio"]["window_found"], false);
+        assert_eq!(health["snap_enabled"], true);
+        assert_eq!(health["daemon"]["port"], 4849);
+        assert_eq!(health["sessions"]["listening"], true);
+        assert!(health.get("probes").is_none());
+
+        fakes.plugin.installed.store(true, Ordering::SeqCst);
+        fakes.plugin.up_to_date.store(true, Ordering::SeqCst);
+        *fakes.daemon.state.lock().unwrap() = DaemonState::External;
+        let (_, health) = send_json(app, Method::GET, "/v1/health", "").await;
+        assert_eq!(health["status"], "ok");
+        assert_eq!(health["daemon"]["reachable"], true);
+    }
+
+    #[tokio::test]
+    async fn plugin_status_reports_update() {
+        let fakes = Fakes::default();
+        fakes.plugin.installed.store(true, Ordering::SeqCst);
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, plugin) = send_json(app, Method::GET, "/v1/plugin/status", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(plugin["installed"], true);
+        assert_eq!(plugin["update_available"], true);
+        assert_eq!(plugin["path"], "/fake/Plugins/Bakable.rbxm");
+    }
+
+    #[tokio::test]
+    async fn plugin_uninstall() {
+        let fakes = Fakes::default();
+        fakes.plugin.installed.store(true, Ordering::SeqCst);
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, json) = send_json(app.clone(), Method::POST, "/v1/plugin/uninstall", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["message"], "Plugin uninstalled");
+        assert!(!fakes.plugin.installed.load(Ordering::SeqCst));
+
+        let (status, json) = send_json(app, Method::POST, "/v1/plugin/uninstall", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["message"], "Plugin was not installed");
+    }
+
+    #[tokio::test]
+    async fn plugins_can_be_disabled_and_imported() {
+        let fakes = Fakes::default();
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, imported) =
+            send_json(app.clone(), Method::POST, "/v1/plugins/import", r#"{"path":"/tmp/Tool.lua"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(imported["path"], "/fake/Plugins/Tool.lua");
+
+        let (status, disabled) =
+            send_json(app.clone(), Method::POST, "/v1/plugins/disable", r#"{"name":"Tool.lua"}"#).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(disabled["enabled"], false);
+
+        let (_, listed) = send_json(app.clone(), Method::GET, "/v1/plugins", "").await;
+        assert_eq!(listed["plugins"][0]["name"], "Tool.lua");
+        assert_eq!(listed["plugins"][0]["enabled"], false);
+
+        let (status, json) = send_json(app, Method::POST, "/v1/plugins/enable", r#"{"name":"Nope.lua"}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["code"], "PLUGIN_NOT_MOVED");
+        assert_eq!(json["details"]["name"], "Nope.lua");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deep_health_probes_with_timeout() {
+        let fakes = Fakes::default();
+        fakes.plugin.installed.store(true, Ordering::SeqCst);
+        fakes.plugin.up_to_date.store(true, Ordering::SeqCst);
+        *fakes.capture.bounds.lock().unwrap() = Some((0, 0, 800, 600));
+        *fakes.daemon.state.lock().unwrap() = DaemonState::External;
+        fakes.daemon.hang.store(true, Ordering::SeqCst);
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, health) = send_json(app, Method::GET, "/v1/health?deep=1", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(health["status"], "degraded");
+        assert_eq!(health["daemon"]["reachable"], false);
+        assert_eq!(health["studio"]["window_found"], true);
+
+        let probes = health["probes"].as_array().unwrap();
+        let names: Vec<&str> = probes.iter().filter_map(|p| p["name"].as_str()).collect();
+        assert_eq!(names, vec!["daemon", "plugin", "studio"]);
+        assert_eq!(probes[0]["ok"], false);
+        assert!(probes[0]["error"].as_str().unwrap().contains("timed out"));
+        assert_eq!(probes[1]["ok"], true);
+    }
+
+    #[tokio::test]
+    async fn capture_returns_png_or_base64() {
+        let fakes = Fakes::default();
+        fakes.capture.permission.store(true, Ordering::SeqCst);
+        *fakes.capture.png.lock().unwrap() = Some(b"\x89PNG".to_vec());
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, body) = send(app.clone(), Method::GET, "/v1/capture", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, b"\x89PNG");
+
+        let (status, json) = send_json(app, Method::GET, "/v1/capture?format=base64", "").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["base64"], "iVBORw==");
+        assert_eq!(json["media_type"], "image/png");
+    }
+
+    #[tokio::test]
+    async fn capture_without_permission_or_studio_fails() {
+        let fakes = Fakes::default();
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, json) = send_json(app.clone(), Method::GET, "/v1/capture", "").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(json["code"], "PERMISSION_DENIED");
+
+        let (_, json) = send_json(app.clone(), Method::GET, "/v1/permission", "").await;
+        assert_eq!(json["granted"], false);
+        assert_eq!(fakes.capture.permission_requests.load(Ordering::SeqCst), 1);
+
+        fakes.capture.permission.store(true, Ordering::SeqCst);
+        let (status, json) = send_json(app, Method::GET, "/v1/capture", "").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(json["code"], "CAPTURE_FAILED");
+        assert_eq!(json["retryable"], true);
+    }
+
+    #[tokio::test]
+    async fn listening_requires_speech_permission() {
+        let fakes = Fakes::default();
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, json) = send_json(app.clone(), Method::POST, "/v1/speech/listen", "").await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(json["code"], "PERMISSION_DENIED");
+        assert_eq!(json["retryable"], false);
+        assert_eq!(fakes.speech.permission_requests.load(Ordering::SeqCst), 1);
+
+        fakes.speech.permission.store(true, Ordering::SeqCst);
+        let (_, json) = send_json(app.clone(), Method::POST, "/v1/speech/listen", "").await;
+        assert_eq!(json["success"], true);
+
+        *fakes.speech.transcription.lock().unwrap() = Some("add a part".to_string());
+        let (_, json) = send_json(app.clone(), Method::GET, "/v1/speech/transcription", "").await;
+        assert_eq!(json, serde_json::json!({ "text": "add a part", "listening": true }));
+
+        send_json(app.clone(), Method::POST, "/v1/speech/stop", "").await;
+        let (_, json) = send_json(app, Method::GET, "/v1/speech/status", "").await;
+        assert_eq!(json["listening"], false);
+    }
+
+    #[tokio::test]
+    async fn speak_and_silence() {
+        let fakes = Fakes::default();
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let (status, json) = send_json(app.clone(), Method::POST, "/v1/speech/speak", "{}").await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json["code"], "INVALID_BODY");
+
+        let (status, json) = send_json(app.clone(), Method::POST, "/v1/speech/speak", r#"{"text":" "}"#).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["details"]["field"], "text");
+
+        let (_, json) = send_json(app.clone(), Method::POST, "/v1/speech/speak", r#"{"text":"hi"}"#).await;
+        assert_eq!(json["success"], true);
+        assert_eq!(*fakes.speech.spoken.lock().unwrap(), vec!["hi".to_string()]);
+        assert!(fakes.speech.speaking.load(Ordering::SeqCst));
+
+        send_json(app, Method::POST, "/v1/speech/silence", "").await;
+        assert!(!fakes.speech.speaking.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn mcp_tools_use_injected_state() {
+        let fakes = Fakes::default();
+        fakes.plugin.installed.store(true, Ordering::SeqCst);
+        let app = build_router(fakes.state(), CorsConfig::default());
+
+        let call = r#"{"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"helper.plugin.status"}}"#;
+        let (status, json) = send_json(app, Method::POST, "/v1/mcp", call).await;
+        assert_eq!(status, StatusCode::OK);
+        let plugin = &json["result"]["structuredContent"];
+        assert_eq!(plugin["installed"], true);
+        assert_eq!(plugin["path"], "/fake/Plugins/Bakable.rbxm");
+    }
+
+    #[tokio::test]
+    async fn unversioned_paths_are_deprecated_aliases() {
+        let request = authorized("/health");
+        let response = router(CorsConfig::default()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["deprecation"], "true");
+        assert_eq!(response.headers()[header::LINK], "</v1/health>; rel=\"successor-version\"");
+
+        let request = authorized("/v1/health");
+        let response = router(CorsConfig::default()).oneshot(request).await.unwrap();
+        assert!(!response.headers().contains_key("deprecation"));
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_use_error_envelope() {
+        let (status, json) = send_json(router(CorsConfig::default()), Method::GET, "/v1/nope", "").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(json["code"], "NOT_FOUND");
+        assert_eq!(json["retryable"], false);
+    }
+
+    #[tokio::test]
+    async fn every_documented_operation_is_routed() {
+        let (status, doc) = send_json(router(CorsConfig::default()), Method::GET, "/v1/openapi.json", "").await;
+        assert_eq!(status, StatusCode::OK);
+
+        for (path, item) in doc["paths"].as_object().unwrap() {
+            for method in item.as_object().unwrap().keys() {
+                let method = Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+                let uri = format!("/v1{}", path);
+                let (status, _) = send(router(CorsConfig::default()), method.clone(), &uri, "").await;
+                assert_ne!(status, StatusCode::NOT_FOUND, "{} {}", method, uri);
+                assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{} {}", method, uri);
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn serves_router_over_private_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("helper");
+        let path = dir.join("helper.sock");
+        let listener = bind_unix_listener(&path).await.unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+
+        let server = tokio::spawn(serve_unix(listener, router(CorsConfig::default())));
+
+        let mut stream = tokio::net::UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(
+                format!(
+                    "GET /metrics HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\nConnection: close\r\n\r\n",
+                    TOKEN
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("x-request-id: "));
+
+        // A live socket must not be replaced by a second helper
+        let err = bind_unix_listener(&path).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AddrInUse);
+
+        server.abort();
+    }
+}
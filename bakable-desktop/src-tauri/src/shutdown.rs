@@ -0,0 +1,117 @@
+//! Shutdown coordination
+//!
+//! Quitting from the tray and SIGINT/SIGTERM both go through `run`: it signals
+//! background loops to stop, ends any speech session, stops the daemon, waits
+//! for tracked tasks (the HTTP server draining in-flight requests) up to a
+//! timeout, then cleans up before the process exits.
+
+use std::io::Write;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use crate::state::Speech;
+use crate::{config, daemon, logging};
+
+/// How long tracked tasks get to finish before we exit anyway
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set once shutdown has started
+static REQUESTED: OnceLock<watch::Sender<bool>> = OnceLock::new();
+/// Number of live `TaskGuard`s
+static ACTIVE: OnceLock<watch::Sender<usize>> = OnceLock::new();
+
+fn requested_channel() -> &'static watch::Sender<bool> {
+    REQUESTED.get_or_init(|| watch::channel(false).0)
+}
+
+fn active_channel() -> &'static watch::Sender<usize> {
+    ACTIVE.get_or_init(|| watch::channel(0).0)
+}
+
+/// Keeps shutdown waiting (up to the drain timeout) while alive
+pub struct TaskGuard(());
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        active_channel().send_modify(|active| *active -= 1);
+    }
+}
+
+/// Register a task that must finish before the process exits
+pub fn track() -> TaskGuard {
+    active_channel().send_modify(|active| *active += 1);
+    TaskGuard(())
+}
+
+/// Check if shutdown has started
+pub fn requested() -> bool {
+    *requested_channel().borrow()
+}
+
+/// Resolve once shutdown has started
+pub async fn wait() {
+    let mut requested = requested_channel().subscribe();
+    let _ = requested.wait_for(|requested| *requested).await;
+}
+
+/// Resolve on SIGINT or SIGTERM (Ctrl+C on Windows)
+pub async fn os_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Stop everything gracefully, ending any session on `speech`. Later callers
+/// just wait for the first to finish.
+pub async fn run(speech: Arc<dyn Speech>) {
+    if requested_channel().send_replace(true) {
+        let mut active = active_channel().subscribe();
+        let _ = tokio::time::timeout(DRAIN_TIMEOUT, active.wait_for(|active| *active == 0)).await;
+        return;
+    }
+
+    info!("Shutting down");
+
+    // End any speech session so in-flight speech requests return promptly
+    speech.stop_listening();
+    speech.stop_speaking();
+
+    daemon::stop().await;
+
+    let mut active = active_channel().subscribe();
+    let drained = tokio::time::timeout(DRAIN_TIMEOUT, active.wait_for(|active| *active == 0))
+        .await
+        .is_ok();
+    if !drained {
+        warn!(
+            "Gave up waiting for {} task(s) after {:?}",
+            *active_channel().borrow(),
+            DRAIN_TIMEOUT
+        );
+    }
+
+    config::Discovery::remove();
+
+    info!("Shutdown complete");
+    logging::flush();
+    let _ = std::io::stdout().flush();
+}
@@ -2,11 +2,27 @@
 //!
 //! - Speech-to-Text: SFSpeechRecognizer
 //! - Text-to-Speech: AVSpeechSynthesizer
+//!
+//! Linux builds with the `whisper` feature recognize speech offline instead
+//! (see `whisper`).
 
 use std::ffi::{CStr, CString};
+use std::sync::Arc;
+use std::time::Duration;
 use tracing::{info, error};
 
+use crate::events::{self, Event, Permission};
+use crate::state::Speech;
+use crate::{metrics, shutdown};
+
+/// How often the event monitor polls recognizer and synthesizer state
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Permission is checked every this many polls
+const PERMISSION_POLLS: u32 = 10;
+
 // Link to Swift speech functions
+#[cfg(target_os = "macos")]
 extern "C" {
     fn check_speech_permission() -> bool;
     fn request_speech_permission();
@@ -23,6 +39,42 @@ extern "C" {
     fn sr_string_free(ptr: *mut std::ffi::c_void);
 }
 
+// Stand-ins where the macOS speech APIs are unavailable: no permission,
+// never listening or speaking
+#[cfg(not(target_os = "macos"))]
+mod unsupported {
+    use std::ffi::c_void;
+
+    pub unsafe fn check_speech_permission() -> bool {
+        false
+    }
+    pub unsafe fn request_speech_permission() {}
+    pub unsafe fn start_speech_recognition() -> bool {
+        false
+    }
+    pub unsafe fn stop_speech_recognition() {}
+    pub unsafe fn get_transcription() -> *mut c_void {
+        std::ptr::null_mut()
+    }
+    pub unsafe fn is_listening() -> bool {
+        false
+    }
+    pub unsafe fn speak_text(_text: *const i8) -> bool {
+        false
+    }
+    pub unsafe fn stop_speaking() {}
+    pub unsafe fn is_speaking() -> bool {
+        false
+    }
+    pub unsafe fn sr_string_value(_ptr: *mut c_void) -> *const i8 {
+        std::ptr::null()
+    }
+    pub unsafe fn sr_string_free(_ptr: *mut c_void) {}
+}
+
+#[cfg(not(target_os = "macos"))]
+use unsupported::*;
+
 // MARK: - Speech Recognition (STT)
 
 /// Check if speech recognition permission is granted
@@ -38,7 +90,9 @@ pub fn request_stt_permission() {
 /// Start listening for speech
 pub fn start_listening() -> bool {
     info!("Starting speech recognition");
-    unsafe { start_speech_recognition() }
+    let started = unsafe { start_speech_recognition() };
+    record_session("stt", started);
+    started
 }
 
 /// Stop listening for speech
@@ -87,12 +141,15 @@ pub fn speak(text: &str) -> bool {
         Ok(s) => s,
         Err(_) => {
             error!("Invalid text for TTS");
+            record_session("tts", false);
             return false;
         }
     };
 
     info!("Speaking: {}...", &text[..text.len().min(50)]);
-    unsafe { speak_text(c_string.as_ptr()) }
+    let started = unsafe { speak_text(c_string.as_ptr()) };
+    record_session("tts", started);
+    started
 }
 
 /// Stop speaking
@@ -104,3 +161,65 @@ pub fn stop_tts() {
 pub fn is_currently_speaking() -> bool {
     unsafe { is_speaking() }
 }
+
+/// Count a speech session attempt
+pub fn record_session(kind: &str, started: bool) {
+    let result = if started { "started" } else { "failed" };
+    metrics::SPEECH_SESSIONS.inc(&[("kind", kind), ("result", result)]);
+}
+
+// MARK: - Events
+
+/// Poll `speech` and publish transcript, speaking and permission changes
+/// until shutdown
+pub async fn watch_events(speech: Arc<dyn Speech>) {
+    let mut listening = false;
+    let mut speaking = false;
+    let mut transcript: Option<String> = None;
+    let mut permission: Option<bool> = None;
+    let mut polls: u32 = 0;
+
+    while !shutdown::requested() {
+        let now_listening = speech.is_listening();
+        if now_listening || listening {
+            let text = speech.transcription();
+            if !now_listening {
+                // Session ended: the last text we saw is the final transcript
+                if let Some(text) = text.or_else(|| transcript.take()) {
+                    events::publish(Event::TranscriptFinal { text });
+                }
+                transcript = None;
+            } else if let Some(text) = text {
+                if transcript.as_deref() != Some(text.as_str()) {
+                    events::publish(Event::TranscriptPartial { text: text.clone() });
+                    transcript = Some(text);
+                }
+            }
+        }
+        listening = now_listening;
+
+        let now_speaking = speech.is_speaking();
+        if now_speaking != speaking {
+            events::publish(if now_speaking {
+                Event::SpeakingStarted
+            } else {
+                Event::SpeakingFinished
+            });
+            speaking = now_speaking;
+        }
+
+        if polls.is_multiple_of(PERMISSION_POLLS) {
+            let granted = speech.has_permission();
+            if permission != Some(granted) {
+                events::publish(Event::PermissionChanged {
+                    permission: Permission::Speech,
+                    granted,
+                });
+                permission = Some(granted);
+            }
+        }
+        polls = polls.wrapping_add(1);
+
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -0,0 +1,613 @@
+//! Shared state for the HTTP and MCP handlers
+//!
+//! Handlers reach capture, speech, the desktop, the daemon, the plugin
+//! installer, the plugin manager and the clock through trait objects so the
+//! whole router can run against fakes in tests. The `System*`
+//! implementations forward to the platform modules.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::daemon::{self, DaemonState};
+use crate::plugin::{InstallReport, PluginDiff, PluginStatus, PluginsDirResolution};
+use crate::plugin_manager::ManagedPlugin;
+use crate::{capture, config, plugin, plugin_manager, speech};
+
+pub type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+/// Studio window capture
+pub trait Capture: Send + Sync {
+    fn has_permission(&self) -> bool;
+    fn request_permission(&self);
+    /// PNG of the Studio window, or None if capture failed
+    fn capture_viewport(&self) -> Option<Vec<u8>>;
+    fn window_id(&self) -> Option<i64>;
+    /// `(x, y, width, height)` of the Studio window
+    fn window_bounds(&self) -> Option<(i32, i32, i32, i32)>;
+}
+
+/// Speech recognition and text-to-speech
+pub trait Speech: Send + Sync {
+    fn has_permission(&self) -> bool;
+    fn request_permission(&self);
+    fn start_listening(&self) -> bool;
+    fn stop_listening(&self);
+    fn is_listening(&self) -> bool;
+    fn transcription(&self) -> Option<String>;
+    fn speak(&self, text: &str) -> bool;
+    fn stop_speaking(&self);
+    fn is_speaking(&self) -> bool;
+}
+
+/// Window state outside of capture
+pub trait Desktop: Send + Sync {
+    /// Studio is the frontmost application
+    fn is_studio_focused(&self) -> bool;
+    /// The helper window follows Studio
+    fn is_snap_enabled(&self) -> bool;
+}
+
+/// The supervised Node daemon
+pub trait Daemon: Send + Sync {
+    fn state(&self) -> DaemonState;
+    /// Whether the daemon port accepts a connection within `timeout`
+    fn probe(&self, timeout: Duration) -> BoxFuture<bool>;
+}
+
+/// Studio plugin installation
+pub trait PluginInstaller: Send + Sync {
+    /// Install the plugin if it isn't up to date
+    fn install(&self) -> Result<InstallReport, String>;
+    /// Remove the plugin; Ok(false) if it wasn't installed
+    fn uninstall(&self) -> Result<bool, String>;
+    /// Installed plugin compared with the bundled one
+    fn status(&self) -> PluginStatus;
+    /// Candidate plugins directories and the one installs use
+    fn dirs(&self) -> PluginsDirResolution;
+    /// Installed plugin contents compared with the bundled ones
+    fn diff(&self) -> PluginDiff;
+}
+
+/// Every plugin in the plugins directory, not just Bakable
+pub trait PluginManager: Send + Sync {
+    /// Every plugin file, enabled and disabled
+    fn list(&self) -> Result<Vec<ManagedPlugin>, String>;
+    /// Move a plugin in or out of `Plugins.disabled`
+    fn set_enabled(&self, name: &str, enabled: bool) -> Result<ManagedPlugin, String>;
+    /// Copy a plugin file into the plugins directory
+    fn import(&self, source: &Path) -> Result<ManagedPlugin, String>;
+}
+
+/// Source of time for request latency
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// Where the server is listening
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct ListenInfo {
+    pub port: Option<u16>,
+    pub socket: Option<PathBuf>,
+}
+
+/// Dependencies shared by all handlers
+#[derive(Clone)]
+pub struct AppState {
+    pub capture: Arc<dyn Capture>,
+    pub speech: Arc<dyn Speech>,
+    pub desktop: Arc<dyn Desktop>,
+    pub daemon: Arc<dyn Daemon>,
+    pub plugin: Arc<dyn PluginInstaller>,
+    pub plugins: Arc<dyn PluginManager>,
+    pub clock: Arc<dyn Clock>,
+    /// When the helper started, per `clock`
+    pub started: Instant,
+    /// Set once the listeners are bound
+    pub listen: ListenInfo,
+    /// Bearer token API requests must carry; published in the discovery file
+    pub token: String,
+}
+
+impl AppState {
+    /// State backed by the real platform implementations
+    pub fn system() -> Self {
+        let config = config::Config::load();
+        Self {
+            capture: Arc::new(SystemCapture),
+            speech: system_speech(&config),
+            desktop: Arc::new(SystemDesktop),
+            daemon: Arc::new(SystemDaemon),
+            plugin: Arc::new(SystemPlugin),
+            plugins: Arc::new(SystemPluginManager { config }),
+            clock: Arc::new(SystemClock),
+            started: Instant::now(),
+            listen: ListenInfo::default(),
+            token: config::random_token(),
+        }
+    }
+
+    /// Time since the helper started
+    pub fn uptime(&self) -> Duration {
+        self.clock.now().saturating_duration_since(self.started)
+    }
+}
+
+// MARK: - System Implementations
+
+struct SystemCapture;
+
+impl Capture for SystemCapture {
+    fn has_permission(&self) -> bool {
+        capture::has_capture_permission()
+    }
+
+    fn request_permission(&self) {
+        capture::request_permission()
+    }
+
+    fn capture_viewport(&self) -> Option<Vec<u8>> {
+        capture::capture_studio_viewport()
+    }
+
+    fn window_id(&self) -> Option<i64> {
+        capture::get_studio_window_id()
+    }
+
+    fn window_bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        capture::get_studio_window_bounds()
+    }
+}
+
+/// Offline Whisper recognition, speaking through the OS
+#[cfg(all(target_os = "linux", feature = "whisper"))]
+fn system_speech(config: &config::Config) -> Arc<dyn Speech> {
+    Arc::new(crate::whisper::WhisperSpeech::new(config.speech.clone(), Arc::new(SystemSpeech)))
+}
+
+/// The OS recognizer and synthesizer
+#[cfg(not(all(target_os = "linux", feature = "whisper")))]
+fn system_speech(_config: &config::Config) -> Arc<dyn Speech> {
+    Arc::new(SystemSpeech)
+}
+
+struct SystemSpeech;
+
+impl Speech for SystemSpeech {
+    fn has_permission(&self) -> bool {
+        speech::has_speech_permission()
+    }
+
+    fn request_permission(&self) {
+        speech::request_stt_permission()
+    }
+
+    fn start_listening(&self) -> bool {
+        speech::start_listening()
+    }
+
+    fn stop_listening(&self) {
+        speech::stop_listening()
+    }
+
+    fn is_listening(&self) -> bool {
+        speech::is_currently_listening()
+    }
+
+    fn transcription(&self) -> Option<String> {
+        speech::get_current_transcription()
+    }
+
+    fn speak(&self, text: &str) -> bool {
+        speech::speak(text)
+    }
+
+    fn stop_speaking(&self) {
+        speech::stop_tts()
+    }
+
+    fn is_speaking(&self) -> bool {
+        speech::is_currently_speaking()
+    }
+}
+
+struct SystemDesktop;
+
+impl Desktop for SystemDesktop {
+    fn is_studio_focused(&self) -> bool {
+        capture::is_studio_focused()
+    }
+
+    fn is_snap_enabled(&self) -> bool {
+        crate::SNAP_ENABLED.load(Ordering::SeqCst)
+    }
+}
+
+struct SystemDaemon;
+
+impl Daemon for SystemDaemon {
+    fn state(&self) -> DaemonState {
+        daemon::current_state()
+    }
+
+    fn probe(&self, timeout: Duration) -> BoxFuture<bool> {
+        Box::pin(daemon::is_reachable(timeout))
+    }
+}
+
+struct SystemPlugin;
+
+impl PluginInstaller for SystemPlugin {
+    fn install(&self) -> Result<InstallReport, String> {
+        plugin::install_plugin()
+    }
+
+    fn uninstall(&self) -> Result<bool, String> {
+        plugin::uninstall_plugin()
+    }
+
+    fn status(&self) -> PluginStatus {
+        plugin::plugin_status()
+    }
+
+    fn dirs(&self) -> PluginsDirResolution {
+        plugin::resolve_plugins_dir(&config::Config::load())
+    }
+
+    fn diff(&self) -> PluginDiff {
+        plugin::plugin_diff()
+    }
+}
+
+/// Plugin management in the plugins directory from the config loaded at
+/// startup
+struct SystemPluginManager {
+    config: config::Config,
+}
+
+impl PluginManager for SystemPluginManager {
+    fn list(&self) -> Result<Vec<ManagedPlugin>, String> {
+        plugin_manager::list(&self.config)
+    }
+
+    fn set_enabled(&self, name: &str, enabled: bool) -> Result<ManagedPlugin, String> {
+        if enabled {
+            plugin_manager::enable(&self.config, name)
+        } else {
+            plugin_manager::disable(&self.config, name)
+        }
+    }
+
+    fn import(&self, source: &Path) -> Result<ManagedPlugin, String> {
+        plugin_manager::import(&self.config, source)
+    }
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// MARK: - Fakes
+
+/// In-memory implementations for router and MCP tests
+#[cfg(test)]
+pub(crate) mod fakes {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    pub struct FakeCapture {
+        pub permission: AtomicBool,
+        pub permission_requests: AtomicUsize,
+        /// Returned by `capture_viewport`; None simulates a failed capture
+        pub png: Mutex<Option<Vec<u8>>>,
+        pub bounds: Mutex<Option<(i32, i32, i32, i32)>>,
+    }
+
+    impl Capture for FakeCapture {
+        fn has_permission(&self) -> bool {
+            self.permission.load(Ordering::SeqCst)
+        }
+
+        fn request_permission(&self) {
+            self.permission_requests.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn capture_viewport(&self) -> Option<Vec<u8>> {
+            self.png.lock().unwrap().clone()
+        }
+
+        fn window_id(&self) -> Option<i64> {
+            self.bounds.lock().unwrap().map(|_| 42)
+        }
+
+        fn window_bounds(&self) -> Option<(i32, i32, i32, i32)> {
+            *self.bounds.lock().unwrap()
+        }
+    }
+
+    #[derive(Default)]
+    pub struct FakeSpeech {
+        pub permission: AtomicBool,
+        pub permission_requests: AtomicUsize,
+        pub listening: AtomicBool,
+        pub speaking: AtomicBool,
+        pub transcription: Mutex<Option<String>>,
+        /// Every text passed to `speak`
+        pub spoken: Mutex<Vec<String>>,
+    }
+
+    impl Speech for FakeSpeech {
+        fn has_permission(&self) -> bool {
+            self.permission.load(Ordering::SeqCst)
+        }
+
+        fn request_permission(&self) {
+            self.permission_requests.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn start_listening(&self) -> bool {
+            let started = self.has_permission();
+            self.listening.store(started, Ordering::SeqCst);
+            started
+        }
+
+        fn stop_listening(&self) {
+            self.listening.store(false, Ordering::SeqCst);
+        }
+
+        fn is_listening(&self) -> bool {
+            self.listening.load(Ordering::SeqCst)
+        }
+
+        fn transcription(&self) -> Option<String> {
+            self.transcription.lock().unwrap().clone()
+        }
+
+        fn speak(&self, text: &str) -> bool {
+            self.spoken.lock().unwrap().push(text.to_string());
+            self.speaking.store(true, Ordering::SeqCst);
+            true
+        }
+
+        fn stop_speaking(&self) {
+            self.speaking.store(false, Ordering::SeqCst);
+        }
+
+        fn is_speaking(&self) -> bool {
+            self.speaking.load(Ordering::SeqCst)
+        }
+    }
+
+    #[derive(Default)]
+    pub struct FakeDesktop {
+        pub studio_focused: AtomicBool,
+        pub snap_enabled: AtomicBool,
+    }
+
+    impl Desktop for FakeDesktop {
+        fn is_studio_focused(&self) -> bool {
+            self.studio_focused.load(Ordering::SeqCst)
+        }
+
+        fn is_snap_enabled(&self) -> bool {
+            self.snap_enabled.load(Ordering::SeqCst)
+        }
+    }
+
+    pub struct FakeDaemon {
+        pub state: Mutex<DaemonState>,
+        pub reachable: AtomicBool,
+        /// Make `probe` hang past any timeout
+        pub hang: AtomicBool,
+    }
+
+    impl Default for FakeDaemon {
+        fn default() -> Self {
+            Self {
+                state: Mutex::new(DaemonState::Stopped),
+                reachable: AtomicBool::new(false),
+                hang: AtomicBool::new(false),
+            }
+        }
+    }
+
+    impl Daemon for FakeDaemon {
+        fn state(&self) -> DaemonState {
+            self.state.lock().unwrap().clone()
+        }
+
+        fn probe(&self, _timeout: Duration) -> BoxFuture<bool> {
+            let reachable = self.reachable.load(Ordering::SeqCst);
+            let hang = self.hang.load(Ordering::SeqCst);
+            Box::pin(async move {
+                if hang {
+                    std::future::pending::<()>().await;
+                }
+                reachable
+            })
+        }
+    }
+
+    pub struct FakePlugin {
+        pub installed: AtomicBool,
+        pub up_to_date: AtomicBool,
+        pub path: PathBuf,
+    }
+
+    impl Default for FakePlugin {
+        fn default() -> Self {
+            Self {
+                installed: AtomicBool::new(false),
+                up_to_date: AtomicBool::new(false),
+                path: PathBuf::from("/fake/Plugins/Bakable.rbxm"),
+            }
+        }
+    }
+
+    impl PluginInstaller for FakePlugin {
+        fn install(&self) -> Result<InstallReport, String> {
+            Ok(InstallReport {
+                installed: !self.installed.swap(true, Ordering::SeqCst),
+                channel: plugin::PluginChannel::Stable,
+                path: self.path.clone(),
+                studio_running: false,
+                duplicates: Vec::new(),
+            })
+        }
+
+        fn uninstall(&self) -> Result<bool, String> {
+            Ok(self.installed.swap(false, Ordering::SeqCst))
+        }
+
+        fn status(&self) -> PluginStatus {
+            let installed = self.installed.load(Ordering::SeqCst);
+            let update_available = installed && !self.up_to_date.load(Ordering::SeqCst);
+            PluginStatus {
+                channel: plugin::PluginChannel::Stable,
+                installed,
+                path: Some(self.path.clone()),
+                installed_version: installed.then(|| "0.0.1".to_string()),
+                installed_sha256: None,
+                bundled_version: "0.0.1".to_string(),
+                bundled_sha256: String::new(),
+                available_version: Some("0.0.1".to_string()),
+                available_sha256: None,
+                update_available,
+                rollback_available: false,
+                held_back: false,
+            }
+        }
+
+        fn dirs(&self) -> PluginsDirResolution {
+            PluginsDirResolution {
+                chosen: self.path.parent().map(PathBuf::from),
+                reason: "Fake".to_string(),
+                candidates: Vec::new(),
+            }
+        }
+
+        fn diff(&self) -> PluginDiff {
+            let installed = self.installed.load(Ordering::SeqCst);
+            PluginDiff {
+                installed_path: Some(self.path.clone()),
+                installed: installed.then(Vec::new),
+                installed_error: None,
+                bundled: Vec::new(),
+                identical: installed && self.up_to_date.load(Ordering::SeqCst),
+                changed: Vec::new(),
+                only_installed: Vec::new(),
+                only_bundled: Vec::new(),
+            }
+        }
+    }
+
+    /// Other plugins in a fake plugins directory
+    pub struct FakePluginManager {
+        pub dir: PathBuf,
+        pub plugins: Mutex<Vec<ManagedPlugin>>,
+    }
+
+    impl Default for FakePluginManager {
+        fn default() -> Self {
+            Self {
+                dir: PathBuf::from("/fake/Plugins"),
+                plugins: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl PluginManager for FakePluginManager {
+        fn list(&self) -> Result<Vec<ManagedPlugin>, String> {
+            Ok(self.plugins.lock().unwrap().clone())
+        }
+
+        fn set_enabled(&self, name: &str, enabled: bool) -> Result<ManagedPlugin, String> {
+            let mut plugins = self.plugins.lock().unwrap();
+            let plugin = plugins
+                .iter_mut()
+                .find(|plugin| plugin.name == name)
+                .ok_or_else(|| format!("No plugin named {}", name))?;
+            plugin.enabled = enabled;
+            Ok(plugin.clone())
+        }
+
+        fn import(&self, source: &Path) -> Result<ManagedPlugin, String> {
+            let name = source
+                .file_name()
+                .and_then(|name| name.to_str())
+                .ok_or_else(|| format!("Invalid plugin file name: {:?}", source))?;
+            let plugin = ManagedPlugin {
+                name: name.to_string(),
+                path: self.dir.join(name),
+                size: 0,
+                modified: None,
+                enabled: true,
+                bakable: false,
+            };
+            self.plugins.lock().unwrap().push(plugin.clone());
+            Ok(plugin)
+        }
+    }
+
+    /// Clock that stands still, so every request takes zero time
+    pub struct FakeClock {
+        now: Mutex<Instant>,
+    }
+
+    impl Default for FakeClock {
+        fn default() -> Self {
+            Self {
+                now: Mutex::new(Instant::now()),
+            }
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    /// Bearer token of the fake state
+    pub const TOKEN: &str = "test-token";
+
+    /// A set of fakes; tests keep this to configure and inspect them
+    #[derive(Default)]
+    pub struct Fakes {
+        pub capture: Arc<FakeCapture>,
+        pub speech: Arc<FakeSpeech>,
+        pub desktop: Arc<FakeDesktop>,
+        pub daemon: Arc<FakeDaemon>,
+        pub plugin: Arc<FakePlugin>,
+        pub plugins: Arc<FakePluginManager>,
+        pub clock: Arc<FakeClock>,
+    }
+
+    impl Fakes {
+        pub fn state(&self) -> AppState {
+            AppState {
+                capture: self.capture.clone(),
+                speech: self.speech.clone(),
+                desktop: self.desktop.clone(),
+                daemon: self.daemon.clone(),
+                plugin: self.plugin.clone(),
+                plugins: self.plugins.clone(),
+                clock: self.clock.clone(),
+                started: self.clock.now(),
+                listen: ListenInfo::default(),
+                token: TOKEN.to_string(),
+            }
+        }
+    }
+}
@@ -0,0 +1,624 @@
+//! Offline speech recognition with a Whisper model, for Linux builds with the
+//! `whisper` feature
+//!
+//! Audio is 16 kHz mono 16-bit PCM from the microphone (PulseAudio's `parec`,
+//! else ALSA's `arecord`), a command or a file. One thread reads it while
+//! another transcribes the audio so far every couple of seconds for partial
+//! results, and a last pass when capture ends gives the final transcript.
+//! Inference runs on the CPU; nothing leaves the machine.
+
+use std::fs::File;
+use std::io::{BufReader, Cursor, ErrorKind, Read};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, warn};
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState};
+
+use crate::config::{AudioSourceConfig, SpeechConfig};
+use crate::speech;
+use crate::state::Speech;
+
+/// Whisper models take 16 kHz audio
+const SAMPLE_RATE: usize = 16_000;
+
+/// Audio between partial transcriptions
+const PARTIAL_SAMPLES: usize = 2 * SAMPLE_RATE;
+
+/// Whisper's context window. Longer audio is transcribed in windows of this
+/// length, each one's text kept once the window is full.
+const WINDOW_SAMPLES: usize = 30 * SAMPLE_RATE;
+
+/// Microphone capture commands, in order of preference
+const MICROPHONE_COMMANDS: &[&[&str]] = &[
+    &["parec", "--raw", "--format=s16le", "--rate=16000", "--channels=1"],
+    &["arecord", "-q", "-t", "raw", "-f", "S16_LE", "-r", "16000", "-c", "1"],
+];
+
+/// Turns 16 kHz mono samples in `[-1, 1]` into text
+pub trait Transcriber: Send {
+    fn transcribe(&mut self, samples: &[f32]) -> Result<String, String>;
+}
+
+// MARK: - Model
+
+/// A Whisper model loaded from a ggml file
+struct WhisperModel {
+    state: WhisperState,
+    language: Option<String>,
+    threads: i32,
+}
+
+impl WhisperModel {
+    fn load(path: &Path, language: Option<String>) -> Result<Self, String> {
+        let path_str = path
+            .to_str()
+            .ok_or_else(|| format!("Invalid Whisper model path: {:?}", path))?;
+        if !path.is_file() {
+            return Err(format!("Whisper model not found at {:?}", path));
+        }
+
+        let mut params = WhisperContextParameters::default();
+        params.use_gpu(false);
+        let context = WhisperContext::new_with_params(path_str, params)
+            .map_err(|e| format!("Failed to load Whisper model {:?}: {}", path, e))?;
+        let state = context
+            .create_state()
+            .map_err(|e| format!("Failed to initialise Whisper: {}", e))?;
+
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get().min(8)) as i32;
+        info!("Loaded Whisper model {:?} ({} threads)", path, threads);
+        Ok(Self { state, language, threads })
+    }
+}
+
+impl Transcriber for WhisperModel {
+    fn transcribe(&mut self, samples: &[f32]) -> Result<String, String> {
+        let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+        params.set_n_threads(self.threads);
+        params.set_language(Some(self.language.as_deref().unwrap_or("auto")));
+        params.set_no_context(true);
+        params.set_suppress_blank(true);
+        params.set_print_special(false);
+        params.set_print_progress(false);
+        params.set_print_realtime(false);
+        params.set_print_timestamps(false);
+
+        self.state
+            .full(params, samples)
+            .map_err(|e| format!("Whisper failed: {}", e))?;
+        let segments = self
+            .state
+            .full_n_segments()
+            .map_err(|e| format!("Whisper failed: {}", e))?;
+
+        let mut text = String::new();
+        for segment in 0..segments {
+            let segment = self
+                .state
+                .full_get_segment_text(segment)
+                .map_err(|e| format!("Whisper failed: {}", e))?;
+            text.push_str(&segment);
+        }
+        Ok(text.trim().to_string())
+    }
+}
+
+// MARK: - Audio
+
+/// Raw PCM from `source`, with the capture process if there is one
+fn open_source(source: &AudioSourceConfig) -> Result<(Box<dyn Read + Send>, Option<Child>), String> {
+    match source {
+        AudioSourceConfig::Microphone => {
+            for command in MICROPHONE_COMMANDS {
+                match spawn_capture(command) {
+                    Ok(capture) => return Ok(capture),
+                    Err(e) => warn!("{}", e),
+                }
+            }
+            Err("No microphone capture available; install PulseAudio (parec) or ALSA (arecord) utilities".to_string())
+        }
+        AudioSourceConfig::Command { command } => {
+            spawn_capture(&command.iter().map(String::as_str).collect::<Vec<_>>())
+        }
+        AudioSourceConfig::File { path } => {
+            let file = File::open(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+            Ok((pcm_reader(BufReader::new(file))?, None))
+        }
+    }
+}
+
+fn spawn_capture(command: &[&str]) -> Result<(Box<dyn Read + Send>, Option<Child>), String> {
+    let (program, args) = command
+        .split_first()
+        .ok_or_else(|| "Audio source command is empty".to_string())?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| format!("No output from {}", program))?;
+    info!("Capturing audio with {}", program);
+    Ok((Box::new(stdout), Some(child)))
+}
+
+/// Skip a WAV header, checking the format, or pass raw PCM through
+fn pcm_reader<R: Read + Send + 'static>(mut reader: R) -> Result<Box<dyn Read + Send>, String> {
+    let mut header = [0u8; 12];
+    let read = read_up_to(&mut reader, &mut header)?;
+    if read < 12 || &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        return Ok(Box::new(Cursor::new(header[..read].to_vec()).chain(reader)));
+    }
+
+    loop {
+        let mut chunk = [0u8; 8];
+        reader
+            .read_exact(&mut chunk)
+            .map_err(|_| "WAV file has no data chunk".to_string())?;
+        let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]) as u64;
+        match &chunk[0..4] {
+            b"data" => return Ok(Box::new(reader)),
+            b"fmt " => {
+                let mut format = vec![0u8; size as usize];
+                reader
+                    .read_exact(&mut format)
+                    .map_err(|_| "WAV format chunk is truncated".to_string())?;
+                check_wav_format(&format)?;
+                if size % 2 == 1 {
+                    skip(&mut reader, 1)?;
+                }
+            }
+            // Chunks are padded to an even length
+            _ => skip(&mut reader, size + size % 2)?,
+        }
+    }
+}
+
+/// Only 16 kHz mono 16-bit PCM is accepted; nothing is resampled
+fn check_wav_format(format: &[u8]) -> Result<(), String> {
+    if format.len() < 16 {
+        return Err("WAV format chunk is truncated".to_string());
+    }
+    let encoding = u16::from_le_bytes([format[0], format[1]]);
+    let channels = u16::from_le_bytes([format[2], format[3]]);
+    let rate = u32::from_le_bytes([format[4], format[5], format[6], format[7]]);
+    let bits = u16::from_le_bytes([format[14], format[15]]);
+    if encoding != 1 || channels != 1 || rate != SAMPLE_RATE as u32 || bits != 16 {
+        return Err(format!(
+            "WAV must be 16 kHz mono 16-bit PCM, got {} Hz, {} channel(s), {}-bit",
+            rate, channels, bits
+        ));
+    }
+    Ok(())
+}
+
+fn read_up_to(reader: &mut impl Read, buf: &mut [u8]) -> Result<usize, String> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(format!("Failed to read audio: {}", e)),
+        }
+    }
+    Ok(filled)
+}
+
+fn skip(reader: &mut impl Read, bytes: u64) -> Result<(), String> {
+    let skipped = std::io::copy(&mut reader.take(bytes), &mut std::io::sink())
+        .map_err(|e| format!("Failed to read audio: {}", e))?;
+    match skipped == bytes {
+        true => Ok(()),
+        false => Err("WAV file is truncated".to_string()),
+    }
+}
+
+// MARK: - Sessions
+
+/// One listening session, shared with the threads capturing and transcribing
+/// it
+#[derive(Default)]
+struct Session {
+    listening: AtomicBool,
+    stopping: AtomicBool,
+    transcript: Mutex<Option<String>>,
+    /// Killed on stop, so a blocked read returns
+    capture: Mutex<Option<Child>>,
+}
+
+impl Session {
+    fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        if let Some(child) = self.capture.lock().unwrap().as_mut() {
+            let _ = child.kill();
+        }
+    }
+
+    fn set_transcript(&self, committed: &str, text: &str) {
+        let transcript = format!("{} {}", committed, text).trim().to_string();
+        *self.transcript.lock().unwrap() = (!transcript.is_empty()).then_some(transcript);
+    }
+}
+
+/// Send samples from `reader` to `chunks` until it ends or the session is
+/// stopped, then end the capture process. Never waits on transcription.
+fn capture_audio(mut reader: impl Read, chunks: Sender<Vec<f32>>, session: &Session) {
+    let mut buffer = [0u8; 3200];
+    // Odd byte left over from the previous read
+    let mut carry: Option<u8> = None;
+
+    while !session.stopping.load(Ordering::SeqCst) {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => {
+                // Expected when stop kills the capture process
+                if !session.stopping.load(Ordering::SeqCst) {
+                    error!("Failed to read audio: {}", e);
+                }
+                break;
+            }
+        };
+
+        let mut bytes = &buffer[..read];
+        let mut samples = Vec::with_capacity(read / 2 + 1);
+        if let Some(low) = carry.take() {
+            samples.push(sample(low, bytes[0]));
+            bytes = &bytes[1..];
+        }
+        let pairs = bytes.chunks_exact(2);
+        carry = pairs.remainder().first().copied();
+        samples.extend(pairs.map(|pair| sample(pair[0], pair[1])));
+
+        // The transcriber is gone if its model failed to load
+        if chunks.send(samples).is_err() {
+            break;
+        }
+    }
+
+    if let Some(mut child) = session.capture.lock().unwrap().take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Transcribe `chunks` until capture ends, then publish the final transcript
+/// and mark the session finished
+fn transcribe_audio(chunks: Receiver<Vec<f32>>, transcriber: &mut dyn Transcriber, session: &Session) {
+    // Text of windows that filled up
+    let mut committed = String::new();
+    let mut samples: Vec<f32> = Vec::new();
+    let mut since_partial = 0;
+
+    let mut transcribe = |samples: &[f32]| {
+        transcriber.transcribe(samples).unwrap_or_else(|e| {
+            error!("{}", e);
+            String::new()
+        })
+    };
+
+    for chunk in chunks {
+        since_partial += chunk.len();
+        samples.extend(chunk);
+
+        if samples.len() >= WINDOW_SAMPLES {
+            let text = transcribe(&samples);
+            committed = format!("{} {}", committed, text).trim().to_string();
+            session.set_transcript(&committed, "");
+            samples.clear();
+            since_partial = 0;
+        } else if since_partial >= PARTIAL_SAMPLES {
+            let text = transcribe(&samples);
+            session.set_transcript(&committed, &text);
+            since_partial = 0;
+        }
+    }
+
+    if !samples.is_empty() {
+        let text = transcribe(&samples);
+        session.set_transcript(&committed, &text);
+    } else if committed.is_empty() {
+        warn!("No audio received");
+    }
+    session.listening.store(false, Ordering::SeqCst);
+}
+
+fn sample(low: u8, high: u8) -> f32 {
+    i16::from_le_bytes([low, high]) as f32 / 32768.0
+}
+
+/// Whether `source` can be opened: a capture program is installed, or the
+/// file exists
+fn source_available(source: &AudioSourceConfig) -> bool {
+    match source {
+        AudioSourceConfig::Microphone => MICROPHONE_COMMANDS.iter().any(|command| find_program(command[0])),
+        AudioSourceConfig::Command { command } => command.first().is_some_and(|program| find_program(program)),
+        AudioSourceConfig::File { path } => path.exists(),
+    }
+}
+
+/// `program` as a path, or on `PATH`
+fn find_program(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+// MARK: - Speech
+
+/// Speech with Whisper recognition. The model is loaded by the first
+/// session's transcription thread and kept for later ones; speaking goes to
+/// `tts`.
+pub struct WhisperSpeech {
+    config: SpeechConfig,
+    tts: Arc<dyn Speech>,
+    model: Arc<Mutex<Option<WhisperModel>>>,
+    session: Mutex<Option<Arc<Session>>>,
+}
+
+impl WhisperSpeech {
+    pub fn new(config: SpeechConfig, tts: Arc<dyn Speech>) -> Self {
+        Self {
+            config,
+            tts,
+            model: Arc::new(Mutex::new(None)),
+            session: Mutex::new(None),
+        }
+    }
+
+    fn start_session(&self) -> Result<Arc<Session>, String> {
+        let model_path = self
+            .config
+            .model_path()
+            .ok_or_else(|| "No Whisper model configured".to_string())?;
+        if !model_path.is_file() {
+            return Err(format!("Whisper model not found at {:?}", model_path));
+        }
+
+        let (reader, capture) = open_source(&self.config.audio_source)?;
+        let session = Arc::new(Session {
+            listening: AtomicBool::new(true),
+            capture: Mutex::new(capture),
+            ..Session::default()
+        });
+        let (sender, chunks) = mpsc::channel();
+
+        let capturing = session.clone();
+        let spawned = std::thread::Builder::new()
+            .name("whisper-capture".to_string())
+            .spawn(move || capture_audio(reader, sender, &capturing));
+        if let Err(e) = spawned {
+            session.stop();
+            return Err(format!("Failed to start audio capture: {}", e));
+        }
+
+        let model = self.model.clone();
+        let language = self.config.language.clone();
+        let transcribing = session.clone();
+        let spawned = std::thread::Builder::new()
+            .name("whisper".to_string())
+            .spawn(move || {
+                let mut model = model.lock().unwrap();
+                if model.is_none() {
+                    match WhisperModel::load(&model_path, language) {
+                        Ok(loaded) => *model = Some(loaded),
+                        Err(e) => error!("{}", e),
+                    }
+                }
+                match model.as_mut() {
+                    Some(model) => transcribe_audio(chunks, model, &transcribing),
+                    None => {
+                        transcribing.stop();
+                        transcribing.listening.store(false, Ordering::SeqCst);
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            session.stop();
+            return Err(format!("Failed to start transcription: {}", e));
+        }
+        Ok(session)
+    }
+}
+
+impl Speech for WhisperSpeech {
+    /// There's no OS permission to ask for, only a capture source to find
+    fn has_permission(&self) -> bool {
+        source_available(&self.config.audio_source)
+    }
+
+    fn request_permission(&self) {}
+
+    fn start_listening(&self) -> bool {
+        info!("Starting speech recognition");
+        let started = {
+            let mut session = self.session.lock().unwrap();
+            if let Some(running) = session.as_ref().filter(|s| s.listening.load(Ordering::SeqCst)) {
+                // A stopped session keeps listening until its last pass is done
+                if running.stopping.load(Ordering::SeqCst) {
+                    warn!("Still transcribing the last session");
+                    return false;
+                }
+                return true;
+            }
+            match self.start_session() {
+                Ok(started) => {
+                    *session = Some(started);
+                    true
+                }
+                Err(e) => {
+                    error!("Failed to start offline speech recognition: {}", e);
+                    false
+                }
+            }
+        };
+        speech::record_session("stt", started);
+        started
+    }
+
+    fn stop_listening(&self) {
+        info!("Stopping speech recognition");
+        if let Some(session) = self.session.lock().unwrap().as_ref() {
+            session.stop();
+        }
+    }
+
+    fn is_listening(&self) -> bool {
+        self.session
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|s| s.listening.load(Ordering::SeqCst))
+    }
+
+    fn transcription(&self) -> Option<String> {
+        let session = self.session.lock().unwrap();
+        let transcript = session.as_ref()?.transcript.lock().unwrap().clone();
+        transcript
+    }
+
+    fn speak(&self, text: &str) -> bool {
+        self.tts.speak(text)
+    }
+
+    fn stop_speaking(&self) {
+        self.tts.stop_speaking()
+    }
+
+    fn is_speaking(&self) -> bool {
+        self.tts.is_speaking()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::fakes::FakeSpeech;
+    use std::path::PathBuf;
+
+    /// Reports how many samples it was given
+    #[derive(Default)]
+    struct CountingTranscriber {
+        calls: Vec<usize>,
+    }
+
+    impl Transcriber for CountingTranscriber {
+        fn transcribe(&mut self, samples: &[f32]) -> Result<String, String> {
+            self.calls.push(samples.len());
+            Ok(samples.len().to_string())
+        }
+    }
+
+    fn wav(samples: &[i16], rate: u32) -> Vec<u8> {
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&rate.to_le_bytes());
+        wav.extend_from_slice(&(rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"LIST");
+        wav.extend_from_slice(&3u32.to_le_bytes());
+        wav.extend_from_slice(b"abc\0");
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+        wav
+    }
+
+    #[test]
+    fn transcribes_in_windows_with_partials() {
+        // 31 seconds of audio
+        let pcm = vec![0u8; 31 * SAMPLE_RATE * 2];
+        let mut transcriber = CountingTranscriber::default();
+        let session = Session {
+            listening: AtomicBool::new(true),
+            ..Session::default()
+        };
+
+        let (sender, chunks) = mpsc::channel();
+        capture_audio(Cursor::new(pcm), sender, &session);
+        transcribe_audio(chunks, &mut transcriber, &session);
+
+        // Partials every 2 s, the full 30 s window, then the last second
+        assert_eq!(transcriber.calls.len(), 16);
+        assert_eq!(transcriber.calls[0], PARTIAL_SAMPLES);
+        assert_eq!(transcriber.calls[14], WINDOW_SAMPLES);
+        assert_eq!(transcriber.calls[15], SAMPLE_RATE);
+        assert_eq!(session.transcript.lock().unwrap().as_deref(), Some("480000 16000"));
+        assert!(!session.listening.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn reads_wav_or_raw_pcm() {
+        let mut pcm = Vec::new();
+        pcm_reader(Cursor::new(wav(&[1, -2, 3], 16_000))).unwrap().read_to_end(&mut pcm).unwrap();
+        assert_eq!(pcm, vec![1, 0, 0xfe, 0xff, 3, 0]);
+
+        let mut raw = Vec::new();
+        pcm_reader(Cursor::new(vec![1, 2, 3])).unwrap().read_to_end(&mut raw).unwrap();
+        assert_eq!(raw, vec![1, 2, 3]);
+
+        let error = pcm_reader(Cursor::new(wav(&[0], 44_100))).err().unwrap();
+        assert!(error.contains("44100 Hz"), "{}", error);
+    }
+
+    #[test]
+    fn start_fails_while_a_stopped_session_finishes() {
+        let config = SpeechConfig {
+            model: Some(PathBuf::from("/nonexistent/ggml.bin")),
+            ..SpeechConfig::default()
+        };
+        let speech = WhisperSpeech::new(config, Arc::new(FakeSpeech::default()));
+        let session = Arc::new(Session {
+            listening: AtomicBool::new(true),
+            ..Session::default()
+        });
+        *speech.session.lock().unwrap() = Some(session.clone());
+        assert!(speech.start_listening());
+
+        speech.stop_listening();
+        assert!(speech.is_listening());
+        assert!(!speech.start_listening());
+
+        // Once the last pass is done a new session is started, which fails
+        // here for want of a model
+        session.listening.store(false, Ordering::SeqCst);
+        assert!(!speech.start_listening());
+        assert!(!speech.is_listening());
+    }
+
+    #[test]
+    fn checks_the_audio_source_exists() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("speech.wav");
+        let file = AudioSourceConfig::File { path: path.clone() };
+        assert!(!source_available(&file));
+        std::fs::write(&path, wav(&[0], 16_000)).unwrap();
+        assert!(source_available(&file));
+
+        let command = |program: &str| AudioSourceConfig::Command {
+            command: vec![program.to_string()],
+        };
+        assert!(!source_available(&command("bakable-missing-recorder")));
+        assert!(source_available(&command(path.to_str().unwrap())));
+        assert!(!source_available(&AudioSourceConfig::Command { command: Vec::new() }));
+    }
+}
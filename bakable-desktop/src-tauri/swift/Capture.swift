@@ -80,6 +80,16 @@ public func getRobloxStudioWindowBounds(_ outX: UnsafeMutablePointer<Int32>,
     return false
 }
 
+/// Check if Roblox Studio is the frontmost application
+@_cdecl("is_roblox_studio_frontmost")
+public func isRobloxStudioFrontmost() -> Bool {
+    guard let app = NSWorkspace.shared.frontmostApplication else { return false }
+
+    let name = app.localizedName ?? ""
+    let bundleId = app.bundleIdentifier ?? ""
+    return name.lowercased().contains("roblox") || bundleId.lowercased().contains("roblox")
+}
+
 /// Capture Roblox Studio window and return PNG data
 @_cdecl("capture_roblox_studio_window")
 public func captureRobloxStudioWindow() -> UnsafeMutableRawPointer? {
@@ -1,6 +1,7 @@
 import { useState, useRef, useEffect, KeyboardEvent } from 'react';
 import type { Message, Session } from '../App';
 import MessageBubble from './MessageBubble';
+import { helperFetch } from '../helper';
 import './ChatPanel.css';
 
 export interface ImageAttachment {
@@ -91,7 +92,7 @@ export default function ChatPanel({
     setShowAttachMenu(false);
     try {
       // Request screenshot with base64 data
-      const response = await fetch('http://127.0.0.1:4850/capture?format=base64');
+      const response = await helperFetch('/v1/capture?format=base64');
       if (response.ok) {
         const data = await response.json();
         if (data.base64) {
@@ -180,8 +181,8 @@ export default function ChatPanel({
   const toggleSpeech = async () => {
     try {
       if (isListening) {
-        await fetch('http://127.0.0.1:4850/speech/stop', { method: 'POST' });
-        const response = await fetch('http://127.0.0.1:4850/speech/transcription');
+        await helperFetch('/v1/speech/stop', { method: 'POST' });
+        const response = await helperFetch('/v1/speech/transcription');
         if (response.ok) {
           const data = await response.json();
           if (data.text) {
@@ -190,7 +191,7 @@ export default function ChatPanel({
         }
         setIsListening(false);
       } else {
-        const response = await fetch('http://127.0.0.1:4850/speech/listen', { method: 'POST' });
+        const response = await helperFetch('/v1/speech/listen', { method: 'POST' });
         if (response.ok) {
           setIsListening(true);
         }
@@ -1,6 +1,7 @@
 import { invoke } from '@tauri-apps/api/tauri';
 import { useState, useEffect } from 'react';
 import type { EditorConnection } from '../App';
+import { helperConnection } from '../helper';
 import './Sidebar.css';
 
 interface SelectionItem {
@@ -68,9 +69,13 @@ export default function Sidebar({
 }: SidebarProps) {
   const [snapEnabled, setSnapEnabled] = useState(false);
   const [selection, setSelection] = useState<SelectionItem[]>([]);
+  const [helperAddress, setHelperAddress] = useState('Not running');
 
   useEffect(() => {
     invoke<boolean>('get_snap_status').then(setSnapEnabled).catch(() => {});
+    helperConnection()
+      .then(({ port }) => setHelperAddress(port ? `localhost:${port}` : 'Unix socket'))
+      .catch(() => setHelperAddress('Not running'));
   }, [open]);
 
   // Poll selection when sidebar is open
@@ -247,7 +252,7 @@ export default function Sidebar({
               </div>
               <div className="info-row">
                 <span className="info-label">Helper</span>
-                <span className="info-value">localhost:4850</span>
+                <span className="info-value">{helperAddress}</span>
               </div>
             </div>
           </section>
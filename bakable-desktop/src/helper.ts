@@ -0,0 +1,53 @@
+import { invoke } from '@tauri-apps/api/tauri';
+
+/**
+ * Connection details of the helper server, from its discovery file
+ */
+export interface HelperConnection {
+  port?: number;
+  socket?: string;
+  pid: number;
+  token: string;
+  version: string;
+}
+
+let connection: Promise<HelperConnection> | null = null;
+
+/**
+ * Port and token of the running helper. Cached once the helper is listening.
+ */
+export function helperConnection(): Promise<HelperConnection> {
+  if (!connection) {
+    connection = invoke<HelperConnection>('get_helper_connection').catch((error) => {
+      connection = null;
+      throw error;
+    });
+  }
+  return connection;
+}
+
+/**
+ * Request a `/v1` endpoint on the helper with its bearer token
+ */
+export async function helperFetch(endpoint: string, init: RequestInit = {}): Promise<Response> {
+  const { port, token } = await helperConnection();
+  if (!port) {
+    throw new Error('Helper is only listening on a Unix socket');
+  }
+
+  const headers = new Headers(init.headers);
+  headers.set('Authorization', `Bearer ${token}`);
+  return fetch(`http://127.0.0.1:${port}${endpoint}`, { ...init, headers });
+}
+
+/**
+ * URL of the helper's event stream. WebSockets can't carry the bearer header
+ * from a browser, so the token goes in the query.
+ */
+export async function helperEventsUrl(): Promise<string> {
+  const { port, token } = await helperConnection();
+  if (!port) {
+    throw new Error('Helper is only listening on a Unix socket');
+  }
+  return `ws://127.0.0.1:${port}/v1/ws?token=${encodeURIComponent(token)}`;
+}
@@ -4,6 +4,7 @@ import { program } from 'commander';
 import WebSocket from 'ws';
 import chalk from 'chalk';
 import { exec } from 'child_process';
+import { helperBaseUrl, helperDiscoveryPath, helperFetch, helperSocketPath, readHelperDiscovery } from '../daemon/helper-discovery.js';
 
 const PORT = process.env.STUDIOCTL_PORT || 4848;
 const TOKEN = process.env.STUDIOCTL_TOKEN || '';
@@ -150,6 +151,44 @@ program
     }
   });
 
+// Helper command
+program
+  .command('helper')
+  .description('Check Bakable Desktop helper status')
+  .action(async () => {
+    const discovery = readHelperDiscovery();
+    if (discovery) {
+      console.log(chalk.gray(`  Discovery: ${helperDiscoveryPath()}`));
+      if (discovery.port) {
+        console.log(chalk.gray(`  Port ${discovery.port}, pid ${discovery.pid}, v${discovery.version}`));
+      }
+      if (discovery.socket) {
+        console.log(chalk.gray(`  Socket ${discovery.socket}, pid ${discovery.pid}, v${discovery.version}`));
+      }
+    } else {
+      console.log(chalk.yellow('⚠ No discovery file, trying default port'));
+    }
+
+    try {
+      const response = await helperFetch('/v1/health');
+      const health = await response.json();
+      const address = helperSocketPath() ? `unix:${helperSocketPath()}` : helperBaseUrl();
+      console.log(chalk.green(`✓ Helper is running at ${address}`));
+      console.log(chalk.gray(`  Capture permission: ${health.has_capture_permission}`));
+      console.log(chalk.gray(`  Speech permission: ${health.has_speech_permission}`));
+      if (health.plugin) {
+        console.log(chalk.gray(`  Status: ${health.status}, up ${health.uptime_secs}s`));
+        console.log(chalk.gray(`  Plugin: ${health.plugin.installed ? (health.plugin.up_to_date ? 'up to date' : 'outdated') : 'not installed'}`));
+        console.log(chalk.gray(`  Studio: ${health.studio.window_found ? (health.studio.focused ? 'focused' : 'open') : 'not found'}`));
+        console.log(chalk.gray(`  Daemon: ${health.daemon.reachable ? 'reachable' : 'unreachable'} on port ${health.daemon.port}`));
+      }
+    } catch (err) {
+      console.log(chalk.red('✗ Helper is not running'));
+      console.log(chalk.gray('  Start the Bakable Desktop app'));
+      process.exit(1);
+    }
+  });
+
 // Run command
 program
   .command('run')
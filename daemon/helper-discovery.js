@@ -0,0 +1,118 @@
+import * as fs from 'fs';
+import * as http from 'http';
+import * as os from 'os';
+import * as path from 'path';
+
+/**
+ * Bakable Desktop helper discovery
+ *
+ * The desktop helper writes `helper.json` (port and/or socket, pid, token,
+ * version) into its app data directory once its server is bound. The port may
+ * differ from the default 4850 if that port was taken or configured
+ * differently; `socket` is set when the helper also (or only) serves over a
+ * Unix domain socket.
+ */
+
+const APP_IDENTIFIER = 'ai.bakable.desktop';
+const DEFAULT_HELPER_PORT = 4850;
+
+/**
+ * App data directory used by the desktop helper on this platform
+ */
+function appDataDir() {
+  const home = os.homedir();
+  switch (process.platform) {
+    case 'darwin':
+      return path.join(home, 'Library', 'Application Support', APP_IDENTIFIER);
+    case 'win32':
+      return path.join(process.env.APPDATA || path.join(home, 'AppData', 'Roaming'), APP_IDENTIFIER);
+    default:
+      return path.join(process.env.XDG_DATA_HOME || path.join(home, '.local', 'share'), APP_IDENTIFIER);
+  }
+}
+
+/**
+ * Path to the helper's discovery file
+ */
+export function helperDiscoveryPath() {
+  return path.join(appDataDir(), 'helper.json');
+}
+
+/**
+ * Read the discovery file, or null if the helper has not written one
+ */
+export function readHelperDiscovery() {
+  try {
+    const discovery = JSON.parse(fs.readFileSync(helperDiscoveryPath(), 'utf8'));
+    const valid = typeof discovery.port === 'number' || typeof discovery.socket === 'string';
+    return valid ? discovery : null;
+  } catch {
+    return null;
+  }
+}
+
+/**
+ * Base URL of the helper: BAKABLE_HELPER_PORT, then discovery file, then 4850
+ */
+export function helperBaseUrl() {
+  const port = process.env.BAKABLE_HELPER_PORT
+    || readHelperDiscovery()?.port
+    || DEFAULT_HELPER_PORT;
+  return `http://127.0.0.1:${port}`;
+}
+
+/**
+ * Unix socket path of the helper, or null when it should be reached over TCP.
+ * BAKABLE_HELPER_PORT forces TCP.
+ */
+export function helperSocketPath() {
+  if (process.env.BAKABLE_HELPER_PORT || process.platform === 'win32') {
+    return null;
+  }
+  return readHelperDiscovery()?.socket || null;
+}
+
+/**
+ * Request headers with the helper's bearer token, when it has published one
+ */
+function withHelperToken(headers = {}) {
+  const token = readHelperDiscovery()?.token;
+  return token ? { ...headers, authorization: `Bearer ${token}` } : headers;
+}
+
+/**
+ * Request an endpoint on the helper, preferring its Unix socket when it has
+ * one. Sends the token from the discovery file. Resolves to a fetch
+ * `Response` either way.
+ */
+export async function helperFetch(endpoint, options = {}) {
+  const socketPath = helperSocketPath();
+  if (!socketPath) {
+    return fetch(`${helperBaseUrl()}${endpoint}`, { ...options, headers: withHelperToken(options.headers) });
+  }
+
+  return new Promise((resolve, reject) => {
+    const request = http.request({
+      socketPath,
+      path: endpoint,
+      method: options.method || 'GET',
+      headers: withHelperToken({ host: 'localhost', ...options.headers })
+    }, (response) => {
+      const chunks = [];
+      response.on('data', (chunk) => chunks.push(chunk));
+      response.on('end', () => {
+        const headers = new Headers();
+        for (const [name, value] of Object.entries(response.headers)) {
+          headers.set(name, Array.isArray(value) ? value.join(', ') : value);
+        }
+        resolve(new Response(Buffer.concat(chunks), { status: response.statusCode, headers }));
+      });
+      response.on('error', reject);
+    });
+    request.on('error', reject);
+    if (options.body) {
+      request.write(options.body);
+    }
+    request.end();
+  });
+}
@@ -1,8 +1,6 @@
 import * as fs from 'fs';
 import * as path from 'path';
-
-const TAURI_PORT = 4850;
-const TAURI_BASE_URL = `http://127.0.0.1:${TAURI_PORT}`;
+import { helperFetch } from '../../helper-discovery.js';
 
 /**
  * Studio Tools - Plugin-level Studio features
@@ -20,9 +18,9 @@ const TAURI_BASE_URL = `http://127.0.0.1:${TAURI_PORT}`;
  * Call the Tauri helper app
  */
 async function callTauri(endpoint, options = {}) {
-  const url = `${TAURI_BASE_URL}${endpoint}`;
+  // Re-read discovery each call so a restarted helper on a new port is found
   try {
-    const response = await fetch(url, options);
+    const response = await helperFetch(endpoint, options);
     return response;
   } catch (error) {
     throw new Error(`Tauri helper not running. Start Bakable Desktop app. (${error.message})`);
@@ -226,14 +224,15 @@ export function registerStudioTools(registerTool, callPlugin) {
       }
 
       // Call Tauri helper for screenshot
-      const response = await callTauri('/capture');
+      const response = await callTauri('/v1/capture');
 
       if (!response.ok) {
         const error = await response.json();
         return {
           success: false,
-          error: error.error || 'Capture failed',
+          error: error.message || 'Capture failed',
           code: error.code || 'UNKNOWN',
+          retryable: error.retryable ?? false,
           hint: 'Make sure Bakable Desktop is running and has screen recording permission'
         };
       }
@@ -48,10 +48,22 @@ export type StoreState = {
 local Store = {}
 Store.__index = Store
 
+-- Daemon settings the desktop app writes into the plugin when installing it
+local function installedDaemonConfig(): {[string]: any}
+    local module = script.Parent.Parent:FindFirstChild("DaemonConfig")
+    if not module or not module:IsA("ModuleScript") then
+        return {}
+    end
+    local ok, config = pcall(require, module)
+    return if ok and type(config) == "table" then config else {}
+end
+
+local daemonConfig = installedDaemonConfig()
+
 -- Default state
 local defaultState: StoreState = {
-    daemonUrl = "http://127.0.0.1:4849",
-    daemonToken = "",
+    daemonUrl = daemonConfig.daemonUrl or "http://127.0.0.1:4849",
+    daemonToken = daemonConfig.daemonToken or "",
     connectionStatus = "disconnected",
     lastError = nil,
     revision = 0,